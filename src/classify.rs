@@ -0,0 +1,578 @@
+use crate::{
+    error::{Error, Result},
+    heuristics::{Applied, Heuristics},
+    patterns::Patterns,
+    sniff, Config,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::{
+    cell::OnceCell,
+    collections::HashMap,
+    ffi::OsStr,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Files larger than this are never read to guess their language.
+const MAX_SNIFF_SIZE: u64 = 1024 * 1024;
+
+/// Number of bytes at the start and the end of a file that are searched for
+/// a shebang line or modeline.
+const SNIFF_LEN: usize = 4096;
+
+/// The content of a file being classified, read on first use.
+pub(crate) struct Content<'a> {
+    read: Box<dyn Fn() -> io::Result<Vec<u8>> + 'a>,
+    /// The file the content is read from, whose start and end can be read alone.
+    file: Option<&'a Path>,
+    data: OnceCell<io::Result<Vec<u8>>>,
+    head: OnceCell<Option<Vec<u8>>>,
+    tail: OnceCell<Option<Vec<u8>>>,
+}
+
+impl<'a> Content<'a> {
+    pub(crate) fn new<F: Fn() -> io::Result<Vec<u8>> + 'a>(read: F) -> Self {
+        Self {
+            read: Box::new(read),
+            file: None,
+            data: OnceCell::new(),
+            head: OnceCell::new(),
+            tail: OnceCell::new(),
+        }
+    }
+
+    /// Creates the content of the file at `path`.
+    pub(crate) fn file(path: &'a Path) -> Self {
+        Self {
+            file: Some(path),
+            ..Self::new(move || std::fs::read(path))
+        }
+    }
+
+    /// Returns the content, or `None` if it can't be read.
+    pub(crate) fn get(&self) -> Option<&[u8]> {
+        self.data.get_or_init(|| (self.read)()).as_deref().ok()
+    }
+
+    /// Returns the first [`SNIFF_LEN`] bytes of the content, or `None` if they can't be read.
+    ///
+    /// A file is only read in full if its content has already been read.
+    fn head(&self) -> Option<&[u8]> {
+        if let (Some(path), None) = (self.file, self.data.get()) {
+            return self.head.get_or_init(|| read_head(path).ok()).as_deref();
+        }
+
+        let data = self.get()?;
+        Some(&data[..data.len().min(SNIFF_LEN)])
+    }
+
+    /// Returns the last [`SNIFF_LEN`] bytes of the content, or `None` if they can't be read
+    /// or the content is no longer than [`Content::head`].
+    fn tail(&self) -> Option<&[u8]> {
+        if self.head()?.len() < SNIFF_LEN {
+            return None;
+        }
+
+        if let (Some(path), None) = (self.file, self.data.get()) {
+            return self.tail.get_or_init(|| read_tail(path).ok()).as_deref();
+        }
+
+        let data = self.get()?;
+        Some(&data[data.len().saturating_sub(SNIFF_LEN)..])
+    }
+
+    /// Returns the error that occurred when the content was read, if any.
+    pub(crate) fn error(&self) -> Option<&io::Error> {
+        self.data.get()?.as_ref().err()
+    }
+}
+
+fn read_head(path: &Path) -> io::Result<Vec<u8>> {
+    let mut head = vec![];
+    File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    Ok(head)
+}
+
+fn read_tail(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut tail = vec![];
+    file.seek(SeekFrom::End(-(SNIFF_LEN as i64)))?;
+    file.read_to_end(&mut tail)?;
+    Ok(tail)
+}
+
+/// The rule that decided the language of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Rule<'m> {
+    Filename,
+    FilenamePattern(&'m str),
+    Extension(&'m [u8]),
+    Heuristic(&'m [u8], Applied<'m>),
+    Shebang(String),
+    Modeline(String),
+}
+
+/// The language of a file and the rule that decided it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Classified<'m> {
+    /// Index of the language, or `None` if the rule names a language that isn't configured.
+    pub(crate) lang: Option<usize>,
+    pub(crate) rule: Rule<'m>,
+}
+
+/// Assigns files to the languages of a config.
+pub(crate) struct Classifier<'a> {
+    /// Maps a file name to the indices of the languages using it, by precedence.
+    filenames: HashMap<&'a OsStr, Vec<usize>>,
+    /// File name globs of all languages, by precedence.
+    filename_patterns: GlobSet,
+    /// Maps each glob in `filename_patterns` to the index of its language and the glob itself.
+    filename_pattern_langs: Vec<(usize, &'a str)>,
+    /// Maps an extension to the indices of the languages using it, by precedence.
+    exts: HashMap<&'a [u8], Vec<usize>>,
+    /// Rules to choose between the languages of an extension.
+    heuristics: Heuristics<'a>,
+    /// Maps a shebang interpreter to the indices of the languages using it, by precedence.
+    interpreters: HashMap<&'a str, Vec<usize>>,
+    /// Maps a lowercase modeline file type to the indices of the matching languages,
+    /// by precedence, or `None` if modelines are disabled.
+    modes: Option<HashMap<String, Vec<usize>>>,
+    /// Ignore and include patterns of each language.
+    patterns: Vec<(Patterns, Patterns)>,
+}
+
+impl<'a> Classifier<'a> {
+    /// Creates a classifier for files under `root`.
+    ///
+    /// Fails with [`Error::InvalidPattern`] if a pattern in the config is invalid.
+    pub(crate) fn new<P: AsRef<Path>>(root: P, config: &'a Config) -> Result<Self> {
+        let mut filenames: HashMap<&OsStr, Vec<usize>> = HashMap::new();
+        let mut filename_patterns = GlobSetBuilder::new();
+        let mut filename_pattern_langs = vec![];
+        let mut exts: HashMap<&[u8], Vec<usize>> = HashMap::new();
+        let mut interpreters: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut modes: HashMap<String, Vec<usize>> = HashMap::new();
+
+        // visit languages by precedence, so every list of candidates is sorted by it
+        for index in config.precedence() {
+            let language = &config.language[index];
+
+            for filename in &language.filenames {
+                filenames
+                    .entry(OsStr::new(filename))
+                    .or_default()
+                    .push(index);
+            }
+
+            for pattern in &language.filename_patterns {
+                filename_patterns.add(Glob::new(pattern).map_err(Error::invalid_pattern)?);
+                filename_pattern_langs.push((index, pattern.as_str()));
+            }
+
+            for ext in &language.ext {
+                exts.entry(ext.as_bytes()).or_default().push(index);
+            }
+
+            for interpreter in &language.interpreters {
+                interpreters.entry(interpreter).or_default().push(index);
+            }
+
+            // a modeline may name the language, one of its aliases, extensions or interpreters
+            let names = [&language.lang]
+                .into_iter()
+                .chain(&language.aliases)
+                .chain(&language.ext)
+                .chain(&language.interpreters);
+            for name in names {
+                let langs = modes.entry(name.to_lowercase()).or_default();
+                if !langs.contains(&index) {
+                    langs.push(index);
+                }
+            }
+        }
+
+        let patterns = config
+            .language
+            .iter()
+            .map(|language| {
+                Ok((
+                    Patterns::new(&root, &language.ignore).map_err(Error::invalid_pattern)?,
+                    Patterns::new(&root, &language.include).map_err(Error::invalid_pattern)?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            filenames,
+            filename_patterns: filename_patterns.build().map_err(Error::invalid_pattern)?,
+            filename_pattern_langs,
+            exts,
+            heuristics: Heuristics::new(config).map_err(Error::invalid_pattern)?,
+            interpreters,
+            modes: config.common.modelines.then_some(modes),
+            patterns,
+        })
+    }
+
+    /// Returns the index of the language of the file at `path`, or `None` if it isn't counted.
+    /// A file belongs to at most one language, the first of the matching languages
+    /// by [`Config::precedence`].
+    ///
+    /// Exact file names are looked up first, then file name patterns, then extensions.
+    /// The longest matching extension wins, so `d.ts` is looked up before `ts`.
+    /// Languages that ignore the path, or don't include it, are skipped.
+    ///
+    /// If the file is at most [`MAX_SNIFF_SIZE`] bytes, its content is used as well:
+    /// heuristics choose between the languages of an extension, and files that match
+    /// no language by name are matched by their shebang line and, if enabled, modeline.
+    pub(crate) fn classify(&self, path: &Path, size: u64, content: &Content) -> Option<usize> {
+        self.classify_by(path, size, content, &mut |_| {})?.lang
+    }
+
+    /// Like [`Classifier::classify`], but returns the rule that decided the language,
+    /// and calls `rejected` with each language that matched the file before the decision
+    /// but ignores it or doesn't include it.
+    pub(crate) fn classify_by<'m>(
+        &'m self,
+        path: &'m Path,
+        size: u64,
+        content: &Content,
+        rejected: &mut dyn FnMut(usize),
+    ) -> Option<Classified<'m>> {
+        let file_name = path.file_name()?;
+        let sniffable = size <= MAX_SNIFF_SIZE;
+        let mut applicable = |index: usize| {
+            let applicable = self.is_applicable(index, path);
+            if !applicable {
+                rejected(index);
+            }
+            applicable
+        };
+
+        let by_filename = self
+            .filenames
+            .get(file_name)
+            .into_iter()
+            .flatten()
+            .map(|&index| (index, Rule::Filename));
+
+        let by_filename_pattern = self
+            .filename_patterns
+            .matches(file_name)
+            .into_iter()
+            .map(|i| self.filename_pattern_langs[i])
+            .map(|(index, pattern)| (index, Rule::FilenamePattern(pattern)));
+
+        if let Some((index, rule)) = by_filename
+            .chain(by_filename_pattern)
+            .find(|(index, _)| applicable(*index))
+        {
+            return Some(Classified {
+                lang: Some(index),
+                rule,
+            });
+        }
+
+        // match the raw bytes, so file names that aren't valid UTF-8 still match
+        let file_name = file_name.as_encoded_bytes();
+        let exts = (0..file_name.len())
+            .filter(|&i| file_name[i] == b'.')
+            .map(|i| &file_name[i + 1..]);
+
+        for ext in exts {
+            let first = self
+                .exts
+                .get(ext)
+                .into_iter()
+                .flatten()
+                .copied()
+                .find(|&index| applicable(index));
+
+            let first = match first {
+                Some(first) => first,
+                None => continue,
+            };
+
+            if self.heuristics.contains(ext) {
+                // rules for languages that don't apply are skipped, so another rule or
+                // the first applicable language decides instead
+                let applied = Some(content)
+                    .filter(|_| sniffable)
+                    .and_then(Content::get)
+                    .and_then(|v| self.heuristics.apply(ext, v, &mut applicable));
+                if let Some(applied) = applied {
+                    return Some(Classified {
+                        lang: applied.lang,
+                        rule: Rule::Heuristic(ext, applied),
+                    });
+                }
+            }
+
+            return Some(Classified {
+                lang: Some(first),
+                rule: Rule::Extension(ext),
+            });
+        }
+
+        self.sniff(Some(content).filter(|_| sniffable)?, &mut applicable)
+    }
+
+    /// Returns the language named by the shebang line or modeline of `content`.
+    ///
+    /// Only the start of the content is read, and its end too if modelines are enabled.
+    /// A file that can't be read matches no language here.
+    fn sniff<'m>(
+        &self,
+        content: &Content,
+        applicable: &mut dyn FnMut(usize) -> bool,
+    ) -> Option<Classified<'m>> {
+        let head = content.head()?;
+        let by_shebang = sniff::shebang(head).and_then(|interpreter| {
+            let langs = self
+                .interpreters
+                .get(interpreter.as_str())
+                .or_else(|| self.interpreters.get(sniff::strip_version(&interpreter)))?;
+            Some((langs, Rule::Shebang(interpreter)))
+        });
+
+        let by_modeline = self.modes.as_ref().and_then(|modes| {
+            let mode = sniff::modeline(head, content.tail())?;
+            Some((modes.get(&mode.to_lowercase())?, Rule::Modeline(mode)))
+        });
+
+        by_shebang
+            .into_iter()
+            .chain(by_modeline)
+            .find_map(|(langs, rule)| {
+                let &index = langs.iter().find(|&&index| applicable(index))?;
+                Some(Classified {
+                    lang: Some(index),
+                    rule,
+                })
+            })
+    }
+
+    fn is_applicable(&self, index: usize, path: &Path) -> bool {
+        let (ignore, include) = &self.patterns[index];
+
+        !ignore.is_match_or_any_parents(path, false)
+            && (include.is_empty() || include.is_match_or_any_parents(path, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Classifies `path` without reading its content.
+    fn classify_path(classifier: &Classifier, path: &str) -> Option<usize> {
+        let content = Content::new(|| Err(io::ErrorKind::NotFound.into()));
+        classifier.classify(Path::new(path), 0, &content)
+    }
+
+    #[test]
+    fn classify_test() {
+        let config: Config = "
+language:
+  - lang: TypeScript
+    ext: [ts]
+    ignore: [dist, '*.generated.ts']
+  - lang: Declaration
+    ext: [d.ts]
+  - lang: Transport Stream
+    ext: [ts]
+    include: [media/]
+  - lang: Go
+    ext: [go]
+    include: ['services/*/cmd']
+common:
+  ignore: []
+"
+        .parse()
+        .unwrap();
+        let classifier = Classifier::new(".", &config).unwrap();
+
+        assert_eq!(classify_path(&classifier, "./src/a.ts"), Some(0));
+        assert_eq!(classify_path(&classifier, "./src/a.d.ts"), Some(1));
+        assert_eq!(classify_path(&classifier, "./media/dist/a.ts"), Some(2));
+        assert_eq!(classify_path(&classifier, "./dist/a.ts"), None);
+        assert_eq!(classify_path(&classifier, "./src/a.generated.ts"), None);
+        assert_eq!(
+            classify_path(&classifier, "./services/a/cmd/main.go"),
+            Some(3)
+        );
+        assert_eq!(classify_path(&classifier, "./services/a/main.go"), None);
+        assert_eq!(classify_path(&classifier, "./src/a.rs"), None);
+        assert_eq!(classify_path(&classifier, "./src/ts"), None);
+    }
+
+    #[test]
+    fn classify_filename_test() {
+        let config: Config = "
+language:
+  - lang: Text
+    ext: [txt]
+  - lang: CMake
+    ext: [cmake]
+    filenames: [CMakeLists.txt]
+  - lang: Dockerfile
+    ext: [dockerfile]
+    filenames: [Dockerfile]
+    filename_patterns: ['Dockerfile.*', '*.Dockerfile']
+  - lang: Starlark
+    ext: [bzl]
+    filenames: [BUILD, BUILD.bazel]
+common:
+  ignore: []
+"
+        .parse()
+        .unwrap();
+        let classifier = Classifier::new(".", &config).unwrap();
+
+        assert_eq!(classify_path(&classifier, "./notes.txt"), Some(0));
+        assert_eq!(classify_path(&classifier, "./CMakeLists.txt"), Some(1));
+        assert_eq!(classify_path(&classifier, "./Dockerfile"), Some(2));
+        assert_eq!(classify_path(&classifier, "./Dockerfile.dev"), Some(2));
+        assert_eq!(classify_path(&classifier, "./api.Dockerfile"), Some(2));
+        assert_eq!(classify_path(&classifier, "./a/BUILD.bazel"), Some(3));
+        assert_eq!(classify_path(&classifier, "./a/dockerfile"), None);
+    }
+
+    #[test]
+    fn classify_content_test() {
+        let yaml = "
+language:
+  - lang: Python
+    ext: [py]
+    interpreters: [python]
+  - lang: Shell
+    ext: [sh]
+    interpreters: [bash, sh]
+  - lang: Ruby
+    ext: [rb]
+common:
+  ignore: []
+";
+        let mut config: Config = yaml.parse().unwrap();
+        let classify = |config: &Config, content: &'static str| {
+            let classifier = Classifier::new(".", config).unwrap();
+            let size = content.len() as u64;
+            let content = Content::new(|| Ok(content.as_bytes().to_vec()));
+            classifier.classify(Path::new("./bin/run"), size, &content)
+        };
+
+        assert_eq!(classify(&config, "#!/usr/bin/env python3\n"), Some(0));
+        assert_eq!(classify(&config, "#!/bin/bash\n"), Some(1));
+        assert_eq!(classify(&config, "#!/usr/bin/perl\n"), None);
+        assert_eq!(classify(&config, "# vim: set ft=ruby:\n"), None);
+
+        config.common.modelines = true;
+        assert_eq!(classify(&config, "# vim: set ft=ruby:\n"), Some(2));
+        assert_eq!(classify(&config, "# -*- mode: sh -*-\n"), Some(1));
+        assert_eq!(classify(&config, "#!/bin/sh\n# vim: ft=python"), Some(1));
+
+        // only the start and the end of a file are read, and reading it may fail
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run");
+        let lines = "x\n".repeat(SNIFF_LEN);
+        std::fs::write(&path, format!("{lines}# vim: set ft=ruby:\n")).unwrap();
+        let classifier = Classifier::new(dir.path(), &config).unwrap();
+        let classify = |path: &Path| {
+            let content = Content::file(path);
+            let lang = classifier.classify(path, SNIFF_LEN as u64 * 2, &content);
+            (
+                lang,
+                content.data.get().is_some(),
+                content.error().is_some(),
+            )
+        };
+
+        assert_eq!(classify(&path), (Some(2), false, false));
+        assert_eq!(classify(&dir.path().join("missing")), (None, false, false));
+    }
+
+    #[test]
+    fn classify_heuristics_test() {
+        let config: Config = r"
+language:
+  - lang: C
+    ext: [c, h]
+  - lang: C++
+    ext: [cpp, h]
+    ignore: [legacy]
+  - lang: TypeScript
+    ext: [ts]
+heuristics:
+  - ext: [h]
+    rules:
+      - lang: C++
+        pattern: ['^\s*namespace\b']
+  - ext: [ts]
+    rules:
+      - lang: XML
+        pattern: ['<\?xml', '<TS\b']
+common:
+  ignore: []
+"
+        .parse()
+        .unwrap();
+        let classifier = Classifier::new(".", &config).unwrap();
+        let classify = |path: &str, content: &'static str| {
+            let content = Content::new(|| Ok(content.as_bytes().to_vec()));
+            classifier.classify(Path::new(path), 1, &content)
+        };
+
+        assert_eq!(classify("./a.h", "namespace a {}"), Some(1));
+        assert_eq!(classify("./a.h", "int a;"), Some(0));
+        assert_eq!(classify("./legacy/a.h", "namespace a {}"), Some(0));
+        assert_eq!(classify("./a.ts", "<?xml version=\"1.0\"?>\n<TS>"), None);
+        assert_eq!(classify("./a.ts", "export const a = 1;"), Some(2));
+        assert_eq!(classify_path(&classifier, "./a.h"), Some(0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn classify_non_utf8_test() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let config = Config::default();
+        let classifier = Classifier::new(".", &config).unwrap();
+        let classify = |path: &[u8]| {
+            let content = Content::new(|| Err(io::ErrorKind::NotFound.into()));
+            classifier.classify(Path::new(OsStr::from_bytes(path)), 0, &content)
+        };
+
+        assert_eq!(classify(b"./caf\xe9/r\xe9sum\xe9.rs"), Some(0));
+        assert_eq!(classify(b"./dist/\xff.ts"), None);
+        assert_eq!(classify(b"./src/\xff.ts"), Some(3));
+        assert_eq!(classify(b"./src/a.\xffrs"), None);
+    }
+
+    #[test]
+    fn classify_priority_test() {
+        let config: Config = "
+language:
+  - lang: C
+    ext: [c, h]
+  - lang: C++
+    ext: [cpp, h]
+    filenames: [a.h]
+  - lang: Objective-C
+    ext: [m, h]
+    filename_patterns: ['*.h']
+    priority: 1
+common:
+  ignore: []
+"
+        .parse()
+        .unwrap();
+        let classifier = Classifier::new(".", &config).unwrap();
+
+        assert_eq!(classify_path(&classifier, "./a.h"), Some(1));
+        assert_eq!(classify_path(&classifier, "./b.h"), Some(2));
+        assert_eq!(classify_path(&classifier, "./b.c"), Some(0));
+    }
+}
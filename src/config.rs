@@ -1,35 +1,581 @@
-use serde::Deserialize;
+use crate::{
+    error::{Error, Result},
+    linguist,
+};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 const DEFAULT_CONFIG: &str = include_str!("config.yaml");
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+/// File name of the repository-local config layer.
+pub const LOCAL_CONFIG_FILE_NAME: &str = ".languatage.yaml";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     pub language: Vec<LanguageConfigItem>,
     pub common: CommonConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub heuristics: Vec<HeuristicConfigItem>,
+    /// Where the config was loaded from, in layer order. Empty for configs parsed from a string.
+    /// Not compared by `==`, so equal configs from different places are equal.
+    #[serde(skip)]
+    pub sources: Vec<ConfigSource>,
+    /// The layers that added rules on top of the first source.
+    #[serde(skip)]
+    origins: Origins,
+}
+
+/// The source of each rule that a layer added, recorded by [`Config::with_layers`].
+/// Rules without an entry come from the first of [`Config::sources`], and rules with
+/// a `None` entry were merged with [`Config::merge`] from an unknown source.
+#[derive(Debug, Clone, Default)]
+struct Origins {
+    /// By the name of the language.
+    language: HashMap<String, Option<ConfigSource>>,
+    /// By the pattern.
+    ignore: HashMap<String, Option<ConfigSource>>,
+    /// By the pattern.
+    include: HashMap<String, Option<ConfigSource>>,
+    /// The sources of the first heuristics, which the layers inserted in front of the rest.
+    heuristics: Vec<Option<ConfigSource>>,
+}
+
+impl PartialEq for Config {
+    fn eq(&self, other: &Self) -> bool {
+        self.language == other.language
+            && self.common == other.common
+            && self.heuristics == other.heuristics
+    }
+}
+
+impl Eq for Config {}
+
+/// A place a config or config layer was loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The config embedded in the crate.
+    BuiltIn,
+    /// A YAML file.
+    File(PathBuf),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BuiltIn => write!(f, "built-in"),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl Serialize for ConfigSource {
+    /// Serializes the source as `"built-in"` or the path of the file.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
 }
 
 impl Config {
+    /// Creates a config without heuristics.
     pub fn new(language: Vec<LanguageConfigItem>, common: CommonConfig) -> Self {
-        Self { language, common }
+        Self {
+            language,
+            common,
+            heuristics: vec![],
+            sources: vec![],
+            origins: Origins::default(),
+        }
+    }
+
+    /// Returns the indices of the languages by precedence: higher `priority` first,
+    /// then config order. A file that matches several languages belongs to the first one.
+    pub fn precedence(&self) -> Vec<usize> {
+        let mut indices: Vec<_> = (0..self.language.len()).collect();
+        indices.sort_by_key(|&index| std::cmp::Reverse(self.language[index].priority));
+        indices
+    }
+
+    /// Returns the extensions that belong to more than one language and aren't covered
+    /// by heuristics, with the names of those languages by [`Config::precedence`].
+    /// Files with such an extension are always counted as the first of the languages.
+    /// ```rust
+    /// use languatage::Config;
+    ///
+    /// for (ext, langs) in Config::default().overlapping_extensions() {
+    ///     eprintln!("warning: .{ext} is counted as {}, not {}", langs[0], langs[1..].join(", "));
+    /// }
+    /// ```
+    pub fn overlapping_extensions(&self) -> Vec<(String, Vec<String>)> {
+        let mut overlaps: Vec<(String, Vec<String>)> = vec![];
+
+        for index in self.precedence() {
+            let language = &self.language[index];
+
+            for ext in &language.ext {
+                match overlaps.iter_mut().find(|(v, _)| v == ext) {
+                    Some((_, langs)) => langs.push(language.lang.clone()),
+                    None => overlaps.push((ext.clone(), vec![language.lang.clone()])),
+                }
+            }
+        }
+
+        overlaps.retain(|(ext, langs)| {
+            langs.len() > 1 && !self.heuristics.iter().any(|v| v.ext.contains(ext))
+        });
+        overlaps.sort();
+        overlaps
+    }
+
+    /// Loads a config from the YAML file at `path`.
+    /// ```rust,no_run
+    /// use languatage::Config;
+    ///
+    /// let config: languatage::Result<Config> = Config::from_path("languatage.yaml");
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut config: Self = from_path(path)?;
+        config.sources = vec![ConfigSource::File(path.to_path_buf())];
+        Ok(config)
+    }
+
+    /// Builds the config used to scan `path` by layering, in order,
+    /// the built-in config, the user config (see [`Config::user_config_path`])
+    /// and the nearest [`LOCAL_CONFIG_FILE_NAME`] found by walking up from `path`.
+    /// ```rust
+    /// use languatage::Config;
+    ///
+    /// let config: languatage::Result<Config> = Config::discover(".");
+    /// ```
+    pub fn discover<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::default().with_layers(path)
+    }
+
+    /// Applies the user config and the nearest [`LOCAL_CONFIG_FILE_NAME`] found by walking
+    /// up from `path` on top of this config, like [`Config::discover`] does on top of
+    /// the built-in config.
+    pub fn with_layers<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        let layers = Self::user_config_path()
+            .filter(|path| path.is_file())
+            .into_iter()
+            .chain(Self::find_local_config(path));
+
+        for path in layers {
+            let layer = ConfigLayer::from_path(&path)?;
+            let source = ConfigSource::File(path);
+            self.merge_from(layer, Some(&source));
+            self.sources.push(source);
+        }
+
+        Ok(self)
+    }
+
+    /// Converts GitHub linguist's `languages.yml` to a config with the extensions,
+    /// file names, interpreters, colors, types, aliases and groups of its languages,
+    /// and the `common` settings of the built-in config.
+    ///
+    /// linguist has no comment syntax, so all lines that aren't blank count as code.
+    /// An extension that is the first extension of some languages is only kept for them.
+    /// ```rust,no_run
+    /// use languatage::Config;
+    ///
+    /// let config: languatage::Result<Config> = Config::from_linguist_path("languages.yml");
+    /// ```
+    pub fn from_linguist_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut config = read_path(path, Self::from_linguist)?;
+        config.sources = vec![ConfigSource::File(path.to_path_buf())];
+        Ok(config)
+    }
+
+    /// Converts GitHub linguist's `languages.yml` from a string, see [`Config::from_linguist_path`].
+    pub fn from_linguist(s: &str) -> Result<Self> {
+        let language =
+            linguist::convert(s).map_err(|source| Error::ParseConfig { path: None, source })?;
+        Ok(Self::new(language, Self::default().common))
+    }
+
+    /// Returns the path of the user config, e.g. `~/.config/languatage/config.yaml`.
+    pub fn user_config_path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("languatage").join("config.yaml"))
+    }
+
+    /// Returns the nearest [`LOCAL_CONFIG_FILE_NAME`] in `path` or any of its ancestors.
+    pub fn find_local_config<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+        let path = path.as_ref().canonicalize().ok()?;
+
+        path.ancestors()
+            .map(|dir| dir.join(LOCAL_CONFIG_FILE_NAME))
+            .find(|config| config.is_file())
+    }
+
+    /// Applies `layer` on top of this config.
+    ///
+    /// Languages in the layer replace the existing language with the same `lang`
+    /// or are appended, and languages listed in `remove_language` are dropped.
+    /// Common ignores and includes are appended, and those listed in `common.remove_ignore`
+    /// and `common.remove_include` are dropped.
+    /// Other common settings replace the current ones when set.
+    /// Heuristics are inserted before the current ones, so their rules are tried first.
+    pub fn merge(&mut self, layer: ConfigLayer) {
+        self.merge_from(layer, None);
+    }
+
+    /// Applies `layer` like [`Config::merge`], recording `source` as the source of its rules.
+    fn merge_from(&mut self, layer: ConfigLayer, source: Option<&ConfigSource>) {
+        let ConfigLayer {
+            language,
+            remove_language,
+            common,
+            heuristics,
+        } = layer;
+
+        self.language
+            .retain(|language| !remove_language.contains(&language.lang));
+
+        for language in language {
+            self.origins
+                .language
+                .insert(language.lang.clone(), source.cloned());
+            match self.language.iter_mut().find(|v| v.lang == language.lang) {
+                Some(current) => *current = language,
+                None => self.language.push(language),
+            }
+        }
+
+        merge_patterns(
+            &mut self.common.ignore,
+            &mut self.origins.ignore,
+            common.ignore,
+            &common.remove_ignore,
+            source,
+        );
+        merge_patterns(
+            &mut self.common.include,
+            &mut self.origins.include,
+            common.include,
+            &common.remove_include,
+            source,
+        );
+
+        if let Some(gitignore) = common.gitignore {
+            self.common.gitignore = gitignore;
+        }
+        if let Some(modelines) = common.modelines {
+            self.common.modelines = modelines;
+        }
+
+        let added = vec![source.cloned(); heuristics.len()];
+        self.origins.heuristics.splice(0..0, added);
+        self.heuristics.splice(0..0, heuristics);
+    }
+
+    /// Returns the source of the language named `lang`.
+    pub(crate) fn language_source(&self, lang: &str) -> Option<ConfigSource> {
+        self.origin(self.origins.language.get(lang))
+    }
+
+    /// Returns the source of the `common.ignore` pattern `pattern`.
+    pub(crate) fn ignore_source(&self, pattern: &str) -> Option<ConfigSource> {
+        self.origin(self.origins.ignore.get(pattern))
+    }
+
+    /// Returns the source of the last `common.include` pattern.
+    pub(crate) fn include_source(&self) -> Option<ConfigSource> {
+        let last = self.common.include.last()?;
+        self.origin(self.origins.include.get(last))
+    }
+
+    /// Returns the source of the first heuristic for `ext`, the one that is applied.
+    pub(crate) fn heuristic_source(&self, ext: &[u8]) -> Option<ConfigSource> {
+        let index = self
+            .heuristics
+            .iter()
+            .position(|v| v.ext.iter().any(|v| v.as_bytes() == ext))?;
+        self.origin(self.origins.heuristics.get(index))
+    }
+
+    fn origin(&self, origin: Option<&Option<ConfigSource>>) -> Option<ConfigSource> {
+        match origin {
+            Some(source) => source.clone(),
+            None => self.sources.first().cloned(),
+        }
+    }
+}
+
+/// Removes `remove` from `patterns` and appends the patterns of `add` that are missing,
+/// recording `source` in `origins` for the appended ones.
+fn merge_patterns(
+    patterns: &mut Vec<String>,
+    origins: &mut HashMap<String, Option<ConfigSource>>,
+    add: Vec<String>,
+    remove: &[String],
+    source: Option<&ConfigSource>,
+) {
+    patterns.retain(|pattern| !remove.contains(pattern));
+    origins.retain(|pattern, _| !remove.contains(pattern));
+    for pattern in add {
+        if !patterns.contains(&pattern) {
+            origins.insert(pattern.clone(), source.cloned());
+            patterns.push(pattern);
+        }
+    }
+}
+
+/// Reads a config or config layer from the YAML file at `path`.
+fn from_path<T: FromStr<Err = Error>>(path: &Path) -> Result<T> {
+    read_path(path, str::parse)
+}
+
+/// Reads the file at `path` and parses it with `parse`, adding `path` to parse errors.
+fn read_path<T>(path: &Path, parse: impl FnOnce(&str) -> Result<T>) -> Result<T> {
+    let source = fs::read_to_string(path).map_err(|source| Error::ReadConfig {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    parse(&source).map_err(|err| match err {
+        Error::ParseConfig { source, .. } => Error::ParseConfig {
+            path: Some(path.to_path_buf()),
+            source,
+        },
+        err => err,
+    })
+}
+
+fn parse<T: serde::de::DeserializeOwned>(s: &str) -> Result<T> {
+    serde_yaml::from_str(s).map_err(|source| Error::ParseConfig { path: None, source })
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    /// Parses a config from a YAML string.
+    fn from_str(s: &str) -> Result<Self> {
+        parse(s)
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        serde_yaml::from_str(DEFAULT_CONFIG).expect("src/config.yaml is invalid")
+        let mut config: Self = DEFAULT_CONFIG.parse().expect("src/config.yaml is invalid");
+        config.sources = vec![ConfigSource::BuiltIn];
+        config
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+/// A language and the files that are counted as it.
+///
+/// A file belongs to the language if its name is one of `filenames`, matches one of
+/// the globs in `filename_patterns` or ends with `.` followed by one of `ext`,
+/// checked in this order. Files that match no language by name are matched by
+/// the interpreter of their shebang line (`#!/usr/bin/env python3` matches `python3`
+/// or `python`) against `interpreters`.
+///
+/// If a file matches several languages it belongs to the one with the highest `priority`
+/// (`0` by default), or to the first one in the config if their priorities are equal.
+///
+/// `ignore` and `include` are gitignore-style glob patterns relative to the scanned
+/// directory. A file is only counted as this language if it doesn't match `ignore`
+/// and, unless `include` is empty, matches `include`.
+///
+/// `color`, `type` and `aliases` describe the language itself. `color` is used by
+/// the renderers in [`crate::render`], and `type` (`programming` by default) can be used to
+/// leave out whole kinds of languages with [`crate::StatOptions::exclude_types`].
+///
+/// `line_comment`, `block_comment`, `nested_comments` and `quotes` describe the syntax
+/// used to count code, comment and blank lines. Comment markers inside `quotes` are
+/// ignored, and a backslash escapes the next character in them. Without any comment
+/// syntax, all lines that aren't blank are code.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct LanguageConfigItem {
     pub lang: String,
+    /// Color of the language in bars and charts, as a hex string like `#dea584`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: LanguageType,
+    /// Other names of the language, also accepted in modelines.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Name of the language that this language is counted as, e.g. `TypeScript` for `TSX`.
+    /// The statistics of a group use the color and type of the language with its name,
+    /// if there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub ext: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filenames: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filename_patterns: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interpreters: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub ignore: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+    /// Markers that start a comment running to the end of the line, e.g. `//`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub line_comment: Vec<String>,
+    /// Start and end markers of block comments, e.g. `["/*", "*/"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub block_comment: Vec<(String, String)>,
+    /// Whether block comments can be nested.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nested_comments: bool,
+    /// Start and end delimiters of string literals, e.g. `['"', '"']`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotes: Vec<(String, String)>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+impl LanguageConfigItem {
+    /// Returns the name the language is counted as: its `group`, or its own name.
+    pub fn counted_as(&self) -> &str {
+        self.group.as_deref().unwrap_or(&self.lang)
+    }
+}
+
+/// The kind of a language, as in linguist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum LanguageType {
+    #[default]
+    Programming,
+    Markup,
+    Data,
+    Prose,
+}
+
+impl fmt::Display for LanguageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Programming => "programming",
+            Self::Markup => "markup",
+            Self::Data => "data",
+            Self::Prose => "prose",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Settings that apply to all languages.
+///
+/// `ignore` and `include` are gitignore-style glob patterns relative to the scanned
+/// directory. Files matching `ignore` are skipped, and unless `include` is empty,
+/// only files matching `include` are counted.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct CommonConfig {
     pub ignore: Vec<String>,
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Whether to skip files ignored by `.gitignore`, `.ignore`, `.git/info/exclude`
+    /// and the global git excludes file.
+    #[serde(default = "default_gitignore")]
+    pub gitignore: bool,
+    /// Whether to match files that match no language otherwise by the file type of
+    /// a Vim or Emacs modeline (`vim: ft=ruby`, `-*- mode: python -*-`). The file type
+    /// is compared case-insensitively with the name, aliases, extensions and interpreters
+    /// of each language.
+    #[serde(default)]
+    pub modelines: bool,
+}
+
+fn default_gitignore() -> bool {
+    true
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
+}
+
+/// Rules that choose the language of files with one of `ext`, like linguist's `heuristics.yml`.
+///
+/// Heuristics are used when a file's extension is in `ext` and the extension belongs to
+/// at least one language. The first rule that matches the file's content decides its
+/// language. If no rule matches, the file belongs to the first language with the extension
+/// by [`Config::precedence`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HeuristicConfigItem {
+    pub ext: Vec<String>,
+    pub rules: Vec<HeuristicRule>,
+}
+
+/// A rule of a [`HeuristicConfigItem`].
+///
+/// The rule matches if any of `pattern` matches, or `pattern` is empty, and none of
+/// `negative_pattern` matches. Patterns are regular expressions where `^` and `$` match
+/// at line boundaries. `lang` may name a language that isn't configured, in which case
+/// matching files aren't counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HeuristicRule {
+    pub lang: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pattern: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub negative_pattern: Vec<String>,
+}
+
+/// A partial config that is applied on top of another one with [`Config::merge`].
+/// ```yaml
+/// language:
+///   - lang: Python
+///     ext:
+///       - py
+/// remove_language:
+///   - Vue
+/// common:
+///   ignore:
+///     - build
+///   remove_ignore:
+///     - target
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ConfigLayer {
+    pub language: Vec<LanguageConfigItem>,
+    pub remove_language: Vec<String>,
+    pub common: CommonConfigLayer,
+    pub heuristics: Vec<HeuristicConfigItem>,
+}
+
+impl ConfigLayer {
+    /// Loads a config layer from the YAML file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        from_path(path.as_ref())
+    }
+}
+
+impl FromStr for ConfigLayer {
+    type Err = Error;
+
+    /// Parses a config layer from a YAML string.
+    fn from_str(s: &str) -> Result<Self> {
+        // an empty file is a valid (empty) layer
+        if s.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        parse(s)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CommonConfigLayer {
+    pub ignore: Vec<String>,
+    pub remove_ignore: Vec<String>,
+    pub include: Vec<String>,
+    pub remove_include: Vec<String>,
+    pub gitignore: Option<bool>,
+    pub modelines: Option<bool>,
 }
 
 #[cfg(test)]
@@ -43,9 +589,164 @@ mod tests {
             config.language[0],
             LanguageConfigItem {
                 lang: "Rust".into(),
+                color: Some("#dea584".into()),
+                kind: LanguageType::Programming,
+                aliases: vec!["rs".into()],
                 ext: vec!["rs".into()],
-                ignore: vec![]
+                line_comment: vec!["//".into()],
+                block_comment: vec![("/*".into(), "*/".into())],
+                nested_comments: true,
+                quotes: vec![("\"".into(), "\"".into())],
+                ..Default::default()
             }
-        )
+        );
+        assert_eq!(config.language[4].kind, LanguageType::Markup);
+        assert!(config.overlapping_extensions().is_empty());
+    }
+
+    #[test]
+    fn config_from_str_test() {
+        let config: Config = "
+language:
+  - lang: Python
+    ext:
+      - py
+    ignore:
+      - venv
+common:
+  ignore:
+    - .git
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(config.language.len(), 1);
+        assert_eq!(config.language[0].lang, "Python");
+        assert_eq!(config.common.ignore, vec![".git".to_string()]);
+        assert!(config.common.gitignore);
+
+        assert!("language: 1".parse::<Config>().is_err());
+    }
+
+    #[test]
+    fn config_from_path_test() {
+        let config = Config::from_path("src/config.yaml").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(
+            config.sources,
+            [ConfigSource::File("src/config.yaml".into())]
+        );
+
+        assert!(matches!(
+            Config::from_path("src/no-such-config.yaml"),
+            Err(Error::ReadConfig { .. })
+        ));
+        assert!(matches!(
+            Config::from_path("Cargo.toml"),
+            Err(Error::ParseConfig { path: Some(_), .. })
+        ));
+    }
+
+    #[test]
+    fn config_merge_test() {
+        let mut config = Config::default();
+        let layer: ConfigLayer = "
+language:
+  - lang: Rust
+    ext:
+      - rs
+    ignore:
+      - generated
+  - lang: Python
+    ext:
+      - py
+  - lang: Pascal
+    ext:
+      - pas
+remove_language:
+  - Vue
+common:
+  ignore:
+    - build
+    - .git
+  remove_ignore:
+    - target
+  gitignore: false
+"
+        .parse()
+        .unwrap();
+
+        config.merge(layer);
+
+        let langs: Vec<_> = config.language.iter().map(|v| v.lang.as_str()).collect();
+        assert_eq!(langs[..4], ["Rust", "Go", "JSX", "TypeScript"]);
+        assert_eq!(langs.last(), Some(&"Pascal"));
+        assert!(!langs.contains(&"Vue"));
+        assert_eq!(config.language[0].ignore, vec!["generated".to_string()]);
+        let python = config.language.iter().find(|v| v.lang == "Python").unwrap();
+        assert_eq!(python.ext, ["py"]);
+        assert!(python.interpreters.is_empty());
+        assert_eq!(config.common.ignore, vec![".git", "node_modules", "build"]);
+        assert!(!config.common.gitignore);
+
+        let mut merged = Config::default();
+        merged.merge("".parse().unwrap());
+        assert_eq!(merged, Config::default());
+    }
+
+    #[test]
+    fn overlapping_extensions_test() {
+        let config: Config = "
+language:
+  - lang: C
+    ext: [c, h]
+  - lang: C++
+    ext: [cpp, h]
+  - lang: Objective-C
+    ext: [m, h]
+    priority: 1
+  - lang: MATLAB
+    ext: [m]
+  - lang: Objective-C++
+    ext: [mm]
+  - lang: Other
+    ext: [mm]
+heuristics:
+  - ext: [mm]
+    rules:
+      - lang: Objective-C++
+common:
+  ignore: []
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(config.precedence(), vec![2, 0, 1, 3, 4, 5]);
+        assert_eq!(
+            config.overlapping_extensions(),
+            vec![
+                (
+                    "h".into(),
+                    vec!["Objective-C".into(), "C".into(), "C++".into()]
+                ),
+                ("m".into(), vec!["Objective-C".into(), "MATLAB".into()]),
+            ]
+        );
+        assert!(Config::default().overlapping_extensions().is_empty());
+    }
+
+    #[test]
+    fn find_local_config_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(Config::find_local_config(&nested), None);
+
+        let local = dir.path().join("a").join(LOCAL_CONFIG_FILE_NAME);
+        fs::write(&local, "remove_language: [Go]").unwrap();
+        assert_eq!(
+            Config::find_local_config(&nested),
+            Some(local.canonicalize().unwrap())
+        );
     }
 }
@@ -0,0 +1,170 @@
+use crate::LanguageStat;
+use serde::Serialize;
+use std::fmt;
+
+/// How a language changed between two sets of statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffStatus {
+    /// The language is only in the new statistics.
+    Added,
+    /// The language is only in the old statistics.
+    Removed,
+    Changed,
+    Unchanged,
+}
+
+impl fmt::Display for DiffStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+            Self::Unchanged => "unchanged",
+        };
+        write!(f, "{status}")
+    }
+}
+
+/// The change of a language, from [`diff_stats`].
+///
+/// The changes are from the old to the new statistics, where a language that is missing
+/// on one side counts as `0`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageDiff {
+    pub lang: String,
+    pub status: DiffStatus,
+    /// Change of the size in bytes.
+    pub size: i64,
+    pub files: i64,
+    pub lines: i64,
+    pub code: i64,
+    pub comments: i64,
+    pub blanks: i64,
+    /// Change of the share of the language in percentage points.
+    pub percentage: f64,
+    pub old: Option<LanguageStat>,
+    pub new: Option<LanguageStat>,
+}
+
+/// Compares the statistics of each language in `old` and `new`, like two
+/// [`Report::stats`](crate::Report::stats).
+///
+/// The languages of `new` come first, in their order, followed by the languages that
+/// were removed, in the order of `old`.
+/// ```rust
+/// use languatage::{diff_stats, DiffStatus, LanguageStat};
+///
+/// let old = [LanguageStat {
+///     lang: "JavaScript".into(),
+///     size: 100,
+///     percentage: 100.0,
+///     ..Default::default()
+/// }];
+/// let new = [LanguageStat {
+///     lang: "TypeScript".into(),
+///     size: 120,
+///     percentage: 100.0,
+///     ..Default::default()
+/// }];
+/// let diffs = diff_stats(&old, &new);
+/// assert_eq!(diffs[0].status, DiffStatus::Added);
+/// assert_eq!(diffs[1].size, -100);
+/// ```
+pub fn diff_stats(old: &[LanguageStat], new: &[LanguageStat]) -> Vec<LanguageDiff> {
+    let find = |stats: &[LanguageStat], lang: &str| stats.iter().find(|v| v.lang == lang).cloned();
+
+    let changed = new
+        .iter()
+        .map(|stat| diff(find(old, &stat.lang), Some(stat.clone())));
+    let removed = old
+        .iter()
+        .filter(|stat| find(new, &stat.lang).is_none())
+        .map(|stat| diff(Some(stat.clone()), None));
+
+    changed.chain(removed).collect()
+}
+
+fn diff(old: Option<LanguageStat>, new: Option<LanguageStat>) -> LanguageDiff {
+    let before = old.clone().unwrap_or_default();
+    let after = new.clone().unwrap_or_default();
+    let delta = |f: fn(&LanguageStat) -> u64| f(&after) as i64 - f(&before) as i64;
+
+    let mut diff = LanguageDiff {
+        lang: new
+            .as_ref()
+            .or(old.as_ref())
+            .map(|v| v.lang.clone())
+            .unwrap_or_default(),
+        status: DiffStatus::Unchanged,
+        size: delta(|v| v.size),
+        files: delta(|v| v.files),
+        lines: delta(|v| v.lines),
+        code: delta(|v| v.code),
+        comments: delta(|v| v.comments),
+        blanks: delta(|v| v.blanks),
+        percentage: after.percentage - before.percentage,
+        old,
+        new,
+    };
+
+    let counts = [
+        diff.size,
+        diff.files,
+        diff.lines,
+        diff.code,
+        diff.comments,
+        diff.blanks,
+    ];
+    diff.status = match (&diff.old, &diff.new) {
+        (None, _) => DiffStatus::Added,
+        (_, None) => DiffStatus::Removed,
+        _ if counts.iter().any(|&v| v != 0) || diff.percentage != 0.0 => DiffStatus::Changed,
+        _ => DiffStatus::Unchanged,
+    };
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_stats_test() {
+        let stat = |lang: &str, size, files, percentage| LanguageStat {
+            lang: lang.into(),
+            size,
+            files,
+            percentage,
+            ..Default::default()
+        };
+        let old = [
+            stat("JavaScript", 300, 3, 60.0),
+            stat("Rust", 100, 1, 20.0),
+            stat("Perl", 100, 1, 20.0),
+        ];
+        let new = [
+            stat("TypeScript", 300, 3, 60.0),
+            stat("JavaScript", 100, 1, 20.0),
+            stat("Rust", 100, 1, 20.0),
+        ];
+        let diffs = diff_stats(&old, &new);
+
+        let summary: Vec<_> = diffs
+            .iter()
+            .map(|v| (v.lang.as_str(), v.status, v.size, v.files))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("TypeScript", DiffStatus::Added, 300, 3),
+                ("JavaScript", DiffStatus::Changed, -200, -2),
+                ("Rust", DiffStatus::Unchanged, 0, 0),
+                ("Perl", DiffStatus::Removed, -100, -1),
+            ]
+        );
+        assert_eq!(diffs[1].percentage, -40.0);
+        assert_eq!(diffs[3].new, None);
+    }
+}
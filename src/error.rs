@@ -0,0 +1,129 @@
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::{fmt, io, path::PathBuf};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors that stop languatage from producing statistics.
+///
+/// The enum is non-exhaustive, since some variants only exist with a feature enabled.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A config file couldn't be read.
+    ReadConfig { path: PathBuf, source: io::Error },
+    /// A config couldn't be parsed. `path` is `None` if the config was parsed from a string.
+    ParseConfig {
+        path: Option<PathBuf>,
+        source: serde_yaml::Error,
+    },
+    /// A glob or regular expression in the config is invalid.
+    InvalidPattern(Box<dyn std::error::Error + Send + Sync>),
+    /// The path to scan couldn't be accessed.
+    Io { path: PathBuf, source: io::Error },
+    /// Paths were skipped while scanning in strict mode.
+    Skipped(Vec<Skipped>),
+    /// A path to explain isn't inside the scanned directory.
+    OutsideRoot { path: PathBuf, root: PathBuf },
+    /// A git repository or revision couldn't be read.
+    #[cfg(feature = "git")]
+    Git(git2::Error),
+}
+
+impl Error {
+    pub(crate) fn invalid_pattern<E>(err: E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self::InvalidPattern(err.into())
+    }
+}
+
+#[cfg(feature = "git")]
+impl From<git2::Error> for Error {
+    fn from(err: git2::Error) -> Self {
+        Self::Git(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadConfig { path, .. } => {
+                write!(f, "failed to read config file {}", path.display())
+            }
+            Self::ParseConfig {
+                path: Some(path), ..
+            } => {
+                write!(f, "failed to parse config file {}", path.display())
+            }
+            Self::ParseConfig { path: None, .. } => write!(f, "failed to parse config"),
+            Self::InvalidPattern(err) => write!(f, "invalid pattern in config: {err}"),
+            Self::Io { path, .. } => write!(f, "failed to access {}", path.display()),
+            Self::Skipped(skipped) => write!(f, "{} path(s) were skipped", skipped.len()),
+            Self::OutsideRoot { path, root } => {
+                write!(f, "{} is outside of {}", path.display(), root.display())
+            }
+            #[cfg(feature = "git")]
+            Self::Git(_) => write!(f, "failed to read git repository"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ReadConfig { source, .. } | Self::Io { source, .. } => Some(source),
+            Self::ParseConfig { source, .. } => Some(source),
+            Self::InvalidPattern(err) => Some(err.as_ref()),
+            Self::Skipped(_) | Self::OutsideRoot { .. } => None,
+            #[cfg(feature = "git")]
+            Self::Git(err) => Some(err),
+        }
+    }
+}
+
+/// A path that was left out of the statistics because of an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.reason)
+    }
+}
+
+impl Serialize for Skipped {
+    /// Serializes the path lossily, so that non-UTF-8 paths don't fail,
+    /// and the reason as its message.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Skipped", 2)?;
+        state.serialize_field("path", &self.path.display().to_string())?;
+        state.serialize_field("reason", &self.reason.to_string())?;
+        state.end()
+    }
+}
+
+/// Why a path was skipped, with the message of the underlying error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The directory couldn't be read, or an ignore file in it couldn't be parsed.
+    /// Everything below the path is missing from the statistics.
+    Walk(String),
+    /// The metadata of the file couldn't be read.
+    Metadata(String),
+    /// The file couldn't be read to detect its language.
+    Read(String),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Walk(err) => write!(f, "failed to walk: {err}"),
+            Self::Metadata(err) => write!(f, "failed to read metadata: {err}"),
+            Self::Read(err) => write!(f, "failed to read: {err}"),
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use crate::{
+    classify::{Classified, Classifier, Content, Rule},
+    config::ConfigSource,
+    error::{Error, Result},
+    patterns::Patterns,
+    serialize_path, Config, LanguageType, Slots, StatOptions,
+};
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use serde::Serialize;
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Why a path is or isn't counted, returned by [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Explanation {
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    /// The language the file is counted as, or `None` if it isn't counted.
+    pub lang: Option<String>,
+    /// The rule that decided whether and as which language the path is counted.
+    pub reason: Reason,
+    /// The config layer the rule came from, or `None` if the rule isn't part of the config.
+    pub source: Option<ConfigSource>,
+    /// Languages that matched the file before the decision but ignore it or don't include it.
+    pub rejected: Vec<Rejected>,
+}
+
+/// A language that matched a file but ignores it or doesn't include it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rejected {
+    pub lang: String,
+    /// [`Reason::LanguageIgnore`] or [`Reason::LanguageNotIncluded`].
+    pub reason: Reason,
+    pub source: Option<ConfigSource>,
+}
+
+/// A rule that decides whether and as which language a path is counted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Reason {
+    /// The file name is one of the `filenames` of the language.
+    Filename { name: String },
+    /// The file name matches one of the `filename_patterns` of the language.
+    FilenamePattern { pattern: String },
+    /// The extension is one of the `ext` of the language.
+    Extension { ext: String },
+    /// A heuristic for the extension matched the content. `pattern` is the regular
+    /// expression that matched, or `None` for a rule without patterns.
+    Heuristic {
+        ext: String,
+        lang: String,
+        pattern: Option<String>,
+    },
+    /// The shebang line names an interpreter of the language.
+    Shebang { interpreter: String },
+    /// A modeline names the language.
+    Modeline { mode: String },
+    /// The path is in, or is, a directory whose name starts with `.`.
+    DotDirectory {
+        #[serde(serialize_with = "serialize_path")]
+        dir: PathBuf,
+    },
+    /// The path, or a directory it is in, matches a pattern of `common.ignore`.
+    CommonIgnore {
+        #[serde(serialize_with = "serialize_path")]
+        path: PathBuf,
+        pattern: String,
+    },
+    /// `common.include` is set and doesn't match the file.
+    NotIncluded,
+    /// The path, or a directory it is in, is ignored by git. `file` is the ignore file
+    /// with the pattern, or `None` for the global git excludes.
+    Gitignore {
+        #[serde(serialize_with = "serialize_path")]
+        path: PathBuf,
+        #[serde(serialize_with = "serialize_optional_path")]
+        file: Option<PathBuf>,
+        pattern: String,
+    },
+    /// The language matched the file, but one of its `ignore` patterns matches it.
+    LanguageIgnore { lang: String, pattern: String },
+    /// The language matched the file, but its `include` patterns don't match it.
+    LanguageNotIncluded { lang: String },
+    /// The file belongs to the language, but its type is one of
+    /// [`StatOptions::exclude_types`].
+    ExcludedType {
+        lang: String,
+        #[serde(rename = "type")]
+        kind: LanguageType,
+    },
+    /// The path is a directory, which is walked.
+    Directory,
+    /// No language matches the file.
+    NoMatch,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Filename { name } => write!(f, "file name {name}"),
+            Self::FilenamePattern { pattern } => write!(f, "file name pattern {pattern}"),
+            Self::Extension { ext } => write!(f, "extension .{ext}"),
+            Self::Heuristic {
+                ext,
+                lang,
+                pattern: Some(pattern),
+            } => write!(f, "heuristic for .{ext}: {pattern} matched {lang}"),
+            Self::Heuristic {
+                ext,
+                lang,
+                pattern: None,
+            } => write!(f, "heuristic for .{ext}: fallback to {lang}"),
+            Self::Shebang { interpreter } => write!(f, "shebang interpreter {interpreter}"),
+            Self::Modeline { mode } => write!(f, "modeline {mode}"),
+            Self::DotDirectory { dir } => write!(f, "dot directory {} is skipped", dir.display()),
+            Self::CommonIgnore { path, pattern } => {
+                write!(f, "{} matches common ignore {pattern}", path.display())
+            }
+            Self::NotIncluded => write!(f, "no common include pattern matches"),
+            Self::Gitignore {
+                path,
+                file: Some(file),
+                pattern,
+            } => write!(
+                f,
+                "{} is ignored by {pattern} in {}",
+                path.display(),
+                file.display()
+            ),
+            Self::Gitignore {
+                path,
+                file: None,
+                pattern,
+            } => write!(
+                f,
+                "{} is ignored by {pattern} in the global git excludes",
+                path.display()
+            ),
+            Self::LanguageIgnore { lang, pattern } => write!(f, "{lang} ignores {pattern}"),
+            Self::LanguageNotIncluded { lang } => write!(f, "{lang} doesn't include it"),
+            Self::ExcludedType { lang, kind } => {
+                write!(f, "{lang} is a {kind} language, which is excluded")
+            }
+            Self::Directory => write!(f, "directory"),
+            Self::NoMatch => write!(f, "no language matches"),
+        }
+    }
+}
+
+/// Explains whether and as which language `path` is counted when `root` is scanned
+/// with `config` and `options`, and which config layer the deciding rule came from.
+/// A relative `path` is relative to `root`.
+///
+/// The checks follow the scan: dot directories, `common.ignore`, git ignore files
+/// (if `common.gitignore` is set) and `common.include` are checked for the path and
+/// the directories between `root` and it, then the file is classified, and left out
+/// if the type of its language is one of [`StatOptions::exclude_types`].
+///
+/// Fails with [`Error::OutsideRoot`] if `path` isn't inside `root`.
+/// ```rust
+/// use languatage::{explain, Config, StatOptions};
+///
+/// let options = StatOptions::default();
+/// let explanation = explain(".", "src/lib.rs", &Config::default(), &options).unwrap();
+/// assert_eq!(explanation.lang.as_deref(), Some("Rust"));
+/// ```
+pub fn explain<P: AsRef<Path>, Q: AsRef<Path>>(
+    root: P,
+    path: Q,
+    config: &Config,
+    options: &StatOptions,
+) -> Result<Explanation> {
+    let given = path.as_ref();
+    let canonicalize = |path: &Path| {
+        path.canonicalize().map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    };
+    let root = canonicalize(root.as_ref())?;
+    let path = canonicalize(&root.join(given))?;
+    let relative = path.strip_prefix(&root).map_err(|_| Error::OutsideRoot {
+        path: given.to_path_buf(),
+        root: root.clone(),
+    })?;
+    let is_dir = path.is_dir();
+
+    let explanation = |lang, reason, source| Explanation {
+        path: given.to_path_buf(),
+        lang,
+        reason,
+        source,
+        rejected: vec![],
+    };
+
+    let ignore = Patterns::new(&root, &config.common.ignore).map_err(Error::invalid_pattern)?;
+    let include = Patterns::new(&root, &config.common.include).map_err(Error::invalid_pattern)?;
+    let gitignores = match config.common.gitignore {
+        true => Gitignores::new(&path),
+        false => Gitignores::default(),
+    };
+
+    // the directories between the root and the path, then the path itself
+    let components: Vec<_> = relative.components().collect();
+    let mut current = root.clone();
+    for (i, component) in components.iter().enumerate() {
+        current.push(component);
+        let is_dir = is_dir || i + 1 < components.len();
+
+        if is_dir && component.as_os_str().as_encoded_bytes().starts_with(b".") {
+            let reason = Reason::DotDirectory {
+                dir: current.clone(),
+            };
+            return Ok(explanation(None, reason, None));
+        }
+
+        if let Some(pattern) = ignore.matched(&current, is_dir) {
+            let source = config.ignore_source(pattern);
+            let reason = Reason::CommonIgnore {
+                path: current.clone(),
+                pattern: pattern.to_string(),
+            };
+            return Ok(explanation(None, reason, source));
+        }
+
+        if let Some((file, pattern)) = gitignores.matched(&current, is_dir) {
+            let reason = Reason::Gitignore {
+                path: current.clone(),
+                file,
+                pattern,
+            };
+            return Ok(explanation(None, reason, None));
+        }
+    }
+
+    if is_dir {
+        return Ok(explanation(None, Reason::Directory, None));
+    }
+
+    if !include.is_empty() && !include.is_match_or_any_parents(&path, false) {
+        let source = config.include_source();
+        return Ok(explanation(None, Reason::NotIncluded, source));
+    }
+
+    let size = fs::metadata(&path)
+        .map_err(|source| Error::Io {
+            path: path.clone(),
+            source,
+        })?
+        .len();
+    let content = Content::file(&path);
+    let classifier = Classifier::new(&root, config)?;
+    let mut rejected = vec![];
+    let classified = classifier.classify_by(&path, size, &content, &mut |index| {
+        if !rejected.contains(&index) {
+            rejected.push(index);
+        }
+    });
+
+    if let Some(err) = content.error() {
+        return Err(Error::Io {
+            path: path.clone(),
+            source: io::Error::new(err.kind(), err.to_string()),
+        });
+    }
+
+    let rejected = rejected
+        .into_iter()
+        .map(|index| reject(&root, &path, config, index))
+        .collect::<Result<Vec<_>>>()?;
+
+    // the file is matched, but left out of the statistics with its language
+    let slots = Slots::new(config);
+    let excluded = classified.as_ref().and_then(|v| v.lang).and_then(|index| {
+        let stat = &slots.stats[slots.of_lang[index]];
+        let reason = Reason::ExcludedType {
+            lang: stat.lang.clone(),
+            kind: stat.kind,
+        };
+        let source = config.language_source(&config.language[index].lang);
+        options
+            .exclude_types
+            .contains(&stat.kind)
+            .then_some((reason, source))
+    });
+
+    let mut explanation = match (classified, excluded) {
+        (_, Some((reason, source))) => explanation(None, reason, source),
+        (Some(Classified { lang, rule }), None) => {
+            let source = match &rule {
+                Rule::Heuristic(ext, _) => config.heuristic_source(ext),
+                _ => lang.and_then(|index| config.language_source(&config.language[index].lang)),
+            };
+            let reason = match rule {
+                Rule::Filename => Reason::Filename {
+                    name: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into(),
+                },
+                Rule::FilenamePattern(pattern) => Reason::FilenamePattern {
+                    pattern: pattern.to_string(),
+                },
+                Rule::Extension(ext) => Reason::Extension {
+                    ext: String::from_utf8_lossy(ext).into(),
+                },
+                Rule::Heuristic(ext, applied) => Reason::Heuristic {
+                    ext: String::from_utf8_lossy(ext).into(),
+                    lang: applied.name.to_string(),
+                    pattern: applied.pattern.map(str::to_string),
+                },
+                Rule::Shebang(interpreter) => Reason::Shebang { interpreter },
+                Rule::Modeline(mode) => Reason::Modeline { mode },
+            };
+            let lang = lang.map(|index| config.language[index].counted_as().to_string());
+            explanation(lang, reason, source)
+        }
+        // languages reject the file in the order they are tried, so the first one is
+        // the one that would have counted it; all of them are listed in `rejected`
+        (None, None) => match rejected.first() {
+            Some(first) => explanation(None, first.reason.clone(), first.source.clone()),
+            None => explanation(None, Reason::NoMatch, None),
+        },
+    };
+    explanation.rejected = rejected;
+
+    Ok(explanation)
+}
+
+/// Explains why the language at `index` doesn't count the file at `path`.
+fn reject(root: &Path, path: &Path, config: &Config, index: usize) -> Result<Rejected> {
+    let language = &config.language[index];
+    let ignore = Patterns::new(root, &language.ignore).map_err(Error::invalid_pattern)?;
+
+    // a layer replaces a language as a whole, so its patterns come from the same layer
+    let reason = match ignore.matched_or_any_parents(path, false) {
+        Some(pattern) => Reason::LanguageIgnore {
+            lang: language.lang.clone(),
+            pattern: pattern.to_string(),
+        },
+        None => Reason::LanguageNotIncluded {
+            lang: language.lang.clone(),
+        },
+    };
+
+    Ok(Rejected {
+        lang: language.lang.clone(),
+        reason,
+        source: config.language_source(&language.lang),
+    })
+}
+
+/// The git ignore files that apply to a path, like the scan reads them.
+#[derive(Default)]
+struct Gitignores {
+    global: Option<Gitignore>,
+    /// `.git/info/exclude` and the `.gitignore` and `.ignore` files of the directories
+    /// the path is in, from the lowest precedence to the highest.
+    files: Vec<Gitignore>,
+}
+
+impl Gitignores {
+    fn new(path: &Path) -> Self {
+        let mut files = vec![];
+        let dirs: Vec<_> = path.ancestors().skip(1).collect();
+
+        // patterns of info/exclude are relative to the repository, not to the file
+        if let Some(repo) = dirs.iter().find(|dir| dir.join(".git").is_dir()) {
+            let mut exclude = GitignoreBuilder::new(repo);
+            exclude.add(repo.join(".git/info/exclude"));
+            files.push(exclude.build().unwrap_or_else(|_| Gitignore::empty()));
+        }
+
+        for dir in dirs.iter().rev() {
+            for name in [".gitignore", ".ignore"] {
+                let file = dir.join(name);
+                if file.is_file() {
+                    files.push(Gitignore::new(file).0);
+                }
+            }
+        }
+
+        Self {
+            global: Some(Gitignore::global().0),
+            files,
+        }
+    }
+
+    /// Returns the ignore file and the pattern that ignore `path` itself, if any.
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<(Option<PathBuf>, String)> {
+        let files = self
+            .files
+            .iter()
+            .rev()
+            .filter(|gitignore| path.starts_with(gitignore.path()));
+
+        for gitignore in files.chain(&self.global) {
+            match gitignore.matched(path, is_dir) {
+                Match::Ignore(glob) => {
+                    return Some((glob.from().map(Path::to_path_buf), glob.original().into()))
+                }
+                Match::Whitelist(_) => return None,
+                Match::None => {}
+            }
+        }
+
+        None
+    }
+}
+
+fn serialize_optional_path<S: serde::Serializer>(
+    path: &Option<PathBuf>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match path {
+        Some(path) => serialize_path(path, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn explain_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for path in [
+            ".cache/a.rs",
+            "vendor/b.rs",
+            "gen/c.rs",
+            "src/d.h",
+            "src/e.txt",
+            "tools/f.rs",
+            ".git/info/exclude",
+        ] {
+            fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+        }
+        fs::write(root.join(".cache/a.rs"), "").unwrap();
+        fs::write(root.join("vendor/b.rs"), "").unwrap();
+        fs::write(root.join("gen/c.rs"), "").unwrap();
+        fs::write(root.join("src/d.h"), "namespace app {}\n").unwrap();
+        fs::write(root.join("src/e.txt"), "").unwrap();
+        fs::write(root.join("tools/f.rs"), "").unwrap();
+        fs::write(root.join(".gitignore"), "/gen\n").unwrap();
+        fs::write(root.join(".git/info/exclude"), "/tools\n").unwrap();
+        fs::write(
+            root.join(".languatage.yaml"),
+            "common:\n  ignore: [vendor]\nlanguage:\n  - lang: Rust\n    ext: [rs]\n",
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.common.gitignore = true;
+        let config = config.with_layers(root).unwrap();
+        let layer = ConfigSource::File(root.join(".languatage.yaml"));
+        let explain =
+            |path: &str| explain(root, root.join(path), &config, &StatOptions::default()).unwrap();
+
+        let a = explain(".cache/a.rs");
+        assert_eq!(a.lang, None);
+        assert!(matches!(a.reason, Reason::DotDirectory { .. }));
+
+        let b = explain("vendor/b.rs");
+        assert_eq!(
+            (b.reason, b.source),
+            (
+                Reason::CommonIgnore {
+                    path: root.canonicalize().unwrap().join("vendor"),
+                    pattern: "vendor".into()
+                },
+                Some(layer.clone())
+            )
+        );
+
+        let c = explain("gen/c.rs");
+        assert!(matches!(c.reason, Reason::Gitignore { pattern, .. } if pattern == "/gen"));
+        assert_eq!(c.source, None);
+
+        let f = explain("tools/f.rs");
+        assert!(matches!(f.reason, Reason::Gitignore { pattern, .. } if pattern == "/tools"));
+
+        let d = explain("src/d.h");
+        assert_eq!(d.lang.as_deref(), Some("C++"));
+        assert!(matches!(d.reason, Reason::Heuristic { lang, .. } if lang == "C++"));
+        assert_eq!(d.source, Some(ConfigSource::BuiltIn));
+
+        assert_eq!(explain("src/e.txt").reason, Reason::NoMatch);
+        assert_eq!(explain("src").reason, Reason::Directory);
+
+        fs::write(root.join("src/g.json"), "{}").unwrap();
+        let g = explain("src/g.json");
+        let reason = Reason::ExcludedType {
+            lang: "JSON".into(),
+            kind: LanguageType::Data,
+        };
+        assert_eq!((g.lang, g.reason), (None, reason));
+
+        // a relative path is relative to the root, not to the working directory
+        let options = StatOptions {
+            exclude_types: vec![],
+            ..Default::default()
+        };
+        let g = super::explain(root, "src/g.json", &config, &options).unwrap();
+        assert_eq!(g.lang.as_deref(), Some("JSON"));
+
+        assert!(matches!(
+            super::explain(
+                root.join("src"),
+                root.join("gen/c.rs"),
+                &config,
+                &StatOptions::default()
+            ),
+            Err(Error::OutsideRoot { .. })
+        ));
+    }
+
+    #[test]
+    fn explain_language_ignore_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::write(root.join("tests/a.rs"), "").unwrap();
+        fs::write(root.join("main.rs"), "").unwrap();
+        fs::write(
+            root.join(".languatage.yaml"),
+            "language:
+  - lang: Rust
+    ext: [rs]
+    ignore: [tests]
+  - lang: Rust Script
+    ext: [rs]
+    include: [scripts]
+",
+        )
+        .unwrap();
+        let config = Config::default().with_layers(root).unwrap();
+        let layer = ConfigSource::File(root.join(".languatage.yaml"));
+
+        let options = StatOptions::default();
+        let a = explain(root, root.join("tests/a.rs"), &config, &options).unwrap();
+        let rejected = Rejected {
+            lang: "Rust".into(),
+            reason: Reason::LanguageIgnore {
+                lang: "Rust".into(),
+                pattern: "tests".into(),
+            },
+            source: Some(layer.clone()),
+        };
+        assert_eq!(a.lang, None);
+        assert_eq!(a.reason, rejected.reason);
+        assert_eq!(a.rejected.len(), 2);
+        assert_eq!(a.rejected[0], rejected);
+        assert_eq!(
+            a.rejected[1].reason,
+            Reason::LanguageNotIncluded {
+                lang: "Rust Script".into()
+            }
+        );
+
+        let main = explain(root, root.join("main.rs"), &config, &options).unwrap();
+        assert_eq!(main.lang.as_deref(), Some("Rust"));
+        assert_eq!(main.reason, Reason::Extension { ext: "rs".into() });
+        assert_eq!(main.source, Some(layer));
+    }
+}
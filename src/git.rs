@@ -0,0 +1,669 @@
+use crate::{
+    classify::{Classifier, Content},
+    classify_file,
+    error::{Error, Result, SkipReason, Skipped},
+    patterns::Patterns,
+    summarize_sums, ClassifiedFile, Config, LanguageStat, Report, StatOptions, Sums,
+};
+use git2::{ObjectType, Odb, Oid, Repository, Tree};
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io, mem,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// Returns language usage statistics of the directory at `path` as it is in the git
+/// revision `rev`, such as a commit hash, a branch or a tag.
+///
+/// The files are read from the object database of the repository that `path` is in,
+/// so the working tree is neither read nor changed, and `path` may be a subdirectory
+/// of the repository. The config applies to the paths in the revision as it would
+/// to a checkout of it. If `common.gitignore` is set, that includes the `.gitignore`
+/// and `.ignore` files committed in the revision, and the `info/exclude` file of the
+/// repository and the global git excludes as they are now. Symbolic links and submodules
+/// are skipped, and [`StatOptions::threads`] is ignored.
+///
+/// Fails with [`Error::Git`] if the repository or the revision can't be read.
+/// ```rust
+/// use languatage::{get_stat_at_rev, Config, Report, StatOptions};
+///
+/// let config: Config = Config::default();
+/// let report: languatage::Result<Report> =
+///     get_stat_at_rev(".", "HEAD", &config, &StatOptions::default());
+/// ```
+pub fn get_stat_at_rev<P: AsRef<Path>>(
+    path: P,
+    rev: &str,
+    config: &Config,
+    options: &StatOptions,
+) -> Result<Report> {
+    let dir = GitDir::open(path.as_ref())?;
+    let tree = dir.repo.revparse_single(rev)?.peel_to_tree()?;
+    let Subtotal { sums, skipped } = TreeWalker::new(&dir, config, options)?.count(&tree)?;
+
+    Ok(Report {
+        stats: summarize_sums(config, sums, options),
+        skipped,
+    })
+}
+
+/// How [`get_history`] picks commits from the first-parent history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sampling {
+    /// Every commit.
+    #[default]
+    All,
+    /// Every `n`th commit, starting with the newest one.
+    Every(NonZeroUsize),
+    /// The newest commit, then each commit at least a week older than the previous one picked.
+    Weekly,
+}
+
+/// Options of [`get_history`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryOptions {
+    pub sampling: Sampling,
+    /// Only count this many of the newest commits that are picked.
+    pub limit: Option<usize>,
+}
+
+/// Statistics of a commit, from [`get_history`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryPoint {
+    /// Hash of the commit.
+    pub commit: String,
+    /// Commit time in seconds since the Unix epoch.
+    pub time: i64,
+    /// Commit date in UTC, like `2024-01-31`.
+    pub date: String,
+    /// Statistics of the directory in the commit, like [`Report::stats`].
+    pub stats: Vec<LanguageStat>,
+    /// Paths of the commit that were left out of the statistics because of errors.
+    pub skipped: Vec<Skipped>,
+}
+
+/// Returns the statistics of [`get_stat_at_rev`] for commits in the first-parent history
+/// of `rev`, oldest first.
+///
+/// Trees that are the same as in the previously counted commit aren't walked again,
+/// so counting many commits that change little is fast.
+/// ```rust
+/// use languatage::{get_history, Config, HistoryOptions, HistoryPoint, Sampling, StatOptions};
+///
+/// let config: Config = Config::default();
+/// let history = HistoryOptions {
+///     sampling: Sampling::Weekly,
+///     limit: Some(10),
+/// };
+/// let points: languatage::Result<Vec<HistoryPoint>> =
+///     get_history(".", "HEAD", &config, &StatOptions::default(), &history);
+/// ```
+pub fn get_history<P: AsRef<Path>>(
+    path: P,
+    rev: &str,
+    config: &Config,
+    options: &StatOptions,
+    history: &HistoryOptions,
+) -> Result<Vec<HistoryPoint>> {
+    const WEEK: i64 = 7 * 24 * 60 * 60;
+
+    let dir = GitDir::open(path.as_ref())?;
+    let mut revwalk = dir.repo.revwalk()?;
+    revwalk.simplify_first_parent()?;
+    revwalk.push(dir.repo.revparse_single(rev)?.peel_to_commit()?.id())?;
+
+    let mut commits = vec![];
+    for (i, oid) in revwalk.enumerate() {
+        if history.limit.is_some_and(|limit| commits.len() >= limit) {
+            break;
+        }
+
+        let commit = dir.repo.find_commit(oid?)?;
+        let picked = match history.sampling {
+            Sampling::All => true,
+            Sampling::Every(n) => i % n.get() == 0,
+            Sampling::Weekly => commits.last().is_none_or(|last: &git2::Commit| {
+                last.time().seconds() - commit.time().seconds() >= WEEK
+            }),
+        };
+        if picked {
+            commits.push(commit);
+        }
+    }
+
+    let mut walker = TreeWalker::new(&dir, config, options)?;
+    commits
+        .iter()
+        .rev()
+        .map(|commit| {
+            let Subtotal { sums, skipped } = walker.count(&commit.tree()?)?;
+            let time = commit.time().seconds();
+            Ok(HistoryPoint {
+                commit: commit.id().to_string(),
+                time,
+                date: date(time),
+                stats: summarize_sums(config, sums, options),
+                skipped,
+            })
+        })
+        .collect()
+}
+
+/// Formats seconds since the Unix epoch as a date in UTC, like `2024-01-31`.
+fn date(time: i64) -> String {
+    // days to a civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let days = time.div_euclid(24 * 60 * 60) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// A directory in a git repository.
+pub(crate) struct GitDir {
+    pub(crate) repo: Repository,
+    /// The path of the directory as given.
+    path: PathBuf,
+    /// The working tree of the repository, or the repository itself if it is bare.
+    repo_root: PathBuf,
+    /// The path of the directory relative to `repo_root`.
+    dir: PathBuf,
+}
+
+impl GitDir {
+    /// Opens the repository that the directory at `path` is in.
+    ///
+    /// The directory only has to exist in the revisions that are read, not in the
+    /// working tree, so `path` is resolved from the nearest of its ancestors that exists.
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        let existing = path
+            .ancestors()
+            .find(|ancestor| ancestor.as_os_str().is_empty() || ancestor.exists())
+            .unwrap_or(path);
+        let missing = path.strip_prefix(existing).unwrap_or(Path::new(""));
+        let existing = match existing.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => existing,
+        };
+        let repo = Repository::discover(existing)?;
+
+        // paths in revisions are matched as if they were checked out in the working tree
+        let (repo_root, dir) = match repo.workdir() {
+            Some(workdir) => {
+                let workdir = canonicalize(workdir)?;
+                let dir = canonicalize(existing)?
+                    .join(missing)
+                    .strip_prefix(&workdir)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default();
+                (workdir, dir)
+            }
+            None => (canonicalize(repo.path())?, PathBuf::new()),
+        };
+
+        Ok(Self {
+            repo,
+            path: path.to_path_buf(),
+            repo_root,
+            dir,
+        })
+    }
+
+    /// Returns the path that files in the directory are matched at.
+    fn root(&self) -> PathBuf {
+        self.repo_root.join(&self.dir)
+    }
+}
+
+/// Returns the name of a tree entry from its raw bytes, so that names that aren't valid
+/// UTF-8 are matched like they are in a checkout.
+#[cfg(unix)]
+fn entry_name(name: &[u8]) -> &Path {
+    use std::os::unix::ffi::OsStrExt;
+    Path::new(std::ffi::OsStr::from_bytes(name))
+}
+
+/// Returns the name of a tree entry from its raw bytes, replacing invalid UTF-8.
+#[cfg(not(unix))]
+fn entry_name(name: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(name).as_ref())
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf> {
+    path.canonicalize().map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The sums of the files in a tree by the index of their language, and the paths
+/// that were skipped in it.
+#[derive(Debug, Default)]
+struct Subtotal {
+    sums: Vec<(usize, Sums)>,
+    skipped: Vec<Skipped>,
+}
+
+/// The ignore files that apply to a directory by precedence, lowest first, and the ids
+/// of their blobs.
+#[derive(Clone, Default)]
+struct Gitignores {
+    matchers: Vec<Gitignore>,
+    ids: Vec<Oid>,
+}
+
+impl Gitignores {
+    /// Returns the global git excludes and the `info/exclude` file of the repository,
+    /// which have the lowest precedence like they do in a walk of the working tree.
+    /// They aren't part of the revision, so they have no ids.
+    fn excludes(dir: &GitDir) -> Self {
+        // a missing or invalid file is left out, like the walk of the working tree does
+        let mut exclude = GitignoreBuilder::new(&dir.repo_root);
+        exclude.add(dir.repo.path().join("info").join("exclude"));
+
+        Self {
+            matchers: vec![
+                Gitignore::global().0,
+                exclude.build().unwrap_or_else(|_| Gitignore::empty()),
+            ],
+            ids: vec![],
+        }
+    }
+
+    /// Returns whether the ignore file with the highest precedence that matches `path`
+    /// ignores it.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for gitignore in self.matchers.iter().rev() {
+            match gitignore.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        false
+    }
+}
+
+/// A tree at a path, with the ids of the ignore files that apply to it.
+type TreeKey = (PathBuf, Oid, Vec<Oid>);
+
+/// Walks tree objects like [`crate::walk::walk`] walks a directory, at the paths they
+/// would have in the working tree.
+struct TreeWalker<'a> {
+    dir: &'a GitDir,
+    odb: Odb<'a>,
+    config: &'a Config,
+    ignore: Patterns,
+    include: Patterns,
+    /// The global git excludes and `info/exclude`, which apply to every revision.
+    excludes: Gitignores,
+    classifier: Classifier<'a>,
+    counts_lines: bool,
+    /// Subtotals of the trees visited by the previous [`TreeWalker::count`].
+    previous: HashMap<TreeKey, Rc<Subtotal>>,
+    /// Subtotals of the trees visited by the current [`TreeWalker::count`].
+    current: HashMap<TreeKey, Rc<Subtotal>>,
+}
+
+impl<'a> TreeWalker<'a> {
+    fn new(dir: &'a GitDir, config: &'a Config, options: &StatOptions) -> Result<Self> {
+        let root = dir.root();
+        Ok(Self {
+            dir,
+            odb: dir.repo.odb()?,
+            config,
+            ignore: Patterns::new(&root, &config.common.ignore).map_err(Error::invalid_pattern)?,
+            include: Patterns::new(&root, &config.common.include)
+                .map_err(Error::invalid_pattern)?,
+            excludes: match config.common.gitignore {
+                true => Gitignores::excludes(dir),
+                false => Gitignores::default(),
+            },
+            classifier: Classifier::new(&root, config)?,
+            counts_lines: options.counts_lines(),
+            previous: HashMap::new(),
+            current: HashMap::new(),
+        })
+    }
+
+    /// Returns the subtotal of the directory in `tree`, the root tree of a revision,
+    /// with the skipped paths sorted by path.
+    fn count(&mut self, tree: &Tree) -> Result<Subtotal> {
+        self.previous = mem::take(&mut self.current);
+
+        // ignore files of the directories above the directory apply too
+        let mut tree = tree.clone();
+        let mut gitignores = self.excludes.clone();
+        let mut skipped = vec![];
+        let mut current = self.dir.repo_root.clone();
+        for component in self.dir.dir.components() {
+            self.add_gitignores(&mut gitignores, &tree, &current, &mut skipped);
+            let entry = tree.get_path(Path::new(component.as_os_str()))?;
+            tree = entry.to_object(&self.dir.repo)?.peel_to_tree()?;
+            current.push(component);
+        }
+        let subtotal = self.visit(&tree, &current, &gitignores)?;
+
+        // report paths under the directory as given, like a walk of the working tree does
+        let root = self.dir.root();
+        skipped.extend(subtotal.skipped.iter().cloned());
+        for skipped in &mut skipped {
+            if let Ok(relative) = skipped.path.strip_prefix(&root) {
+                skipped.path = self.dir.path.join(relative);
+            }
+        }
+        skipped.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(Subtotal {
+            sums: subtotal.sums.clone(),
+            skipped,
+        })
+    }
+
+    /// Returns the subtotal of `tree`, the directory at `dir`, reusing the subtotal of
+    /// the same tree at the same path with the same ignore files if it was just visited.
+    fn visit(&mut self, tree: &Tree, dir: &Path, gitignores: &Gitignores) -> Result<Rc<Subtotal>> {
+        let key = (dir.to_path_buf(), tree.id(), gitignores.ids.clone());
+        if let Some(subtotal) = self.current.get(&key).or_else(|| self.previous.get(&key)) {
+            let subtotal = subtotal.clone();
+            self.current.insert(key, subtotal.clone());
+            return Ok(subtotal);
+        }
+
+        let mut skipped = vec![];
+        let mut own = None;
+        if self.config.common.gitignore {
+            let mut with_own = gitignores.clone();
+            self.add_gitignores(&mut with_own, tree, dir, &mut skipped);
+            if with_own.ids.len() > gitignores.ids.len() {
+                own = Some(with_own);
+            }
+        }
+        let gitignores = own.as_ref().unwrap_or(gitignores);
+
+        let mut sums: BTreeMap<usize, Sums> = BTreeMap::new();
+        for entry in tree {
+            let path = dir.join(entry_name(entry.name_bytes()));
+            let is_dir = match (entry.kind(), entry.filemode()) {
+                (Some(ObjectType::Tree), _) => true,
+                // regular and executable files, not symbolic links
+                (Some(ObjectType::Blob), 0o100644 | 0o100755) => false,
+                _ => continue,
+            };
+
+            if is_dir && entry.name_bytes().starts_with(b".") {
+                continue;
+            }
+            if self.ignore.is_match(&path, is_dir) || gitignores.is_ignored(&path, is_dir) {
+                continue;
+            }
+
+            if is_dir {
+                let subtree = self.dir.repo.find_tree(entry.id())?;
+                let subtotal = self.visit(&subtree, &path, gitignores)?;
+                for (lang, subtotal) in &subtotal.sums {
+                    sums.entry(*lang).or_default().add(subtotal);
+                }
+                skipped.extend(subtotal.skipped.iter().cloned());
+                continue;
+            }
+
+            if !self.include.is_empty() && !self.include.is_match_or_any_parents(&path, false) {
+                continue;
+            }
+
+            match self.classify(&path, entry.id()) {
+                Ok(Some(file)) => sums.entry(file.lang).or_default().add(&Sums::of(&file)),
+                Ok(None) => {}
+                Err(reason) => skipped.push(Skipped { path, reason }),
+            }
+        }
+
+        let subtotal = Rc::new(Subtotal {
+            sums: sums.into_iter().collect(),
+            skipped,
+        });
+        self.current.insert(key, subtotal.clone());
+
+        Ok(subtotal)
+    }
+
+    fn classify(&self, path: &Path, oid: Oid) -> Result<Option<ClassifiedFile>, SkipReason> {
+        let (size, _) = self
+            .odb
+            .read_header(oid)
+            .map_err(|err| SkipReason::Metadata(err.to_string()))?;
+        let content = Content::new(|| {
+            let blob = self.dir.repo.find_blob(oid).map_err(io::Error::other)?;
+            Ok(blob.content().to_vec())
+        });
+
+        classify_file(
+            &self.classifier,
+            self.config,
+            self.counts_lines,
+            path,
+            size as u64,
+            &content,
+        )
+    }
+
+    /// Adds the `.gitignore` and `.ignore` files in `tree`, the directory at `dir`,
+    /// to `gitignores` if `common.gitignore` is set.
+    fn add_gitignores(
+        &self,
+        gitignores: &mut Gitignores,
+        tree: &Tree,
+        dir: &Path,
+        skipped: &mut Vec<Skipped>,
+    ) {
+        if !self.config.common.gitignore {
+            return;
+        }
+
+        let mut skip = |path: PathBuf, err: &dyn ToString| {
+            skipped.push(Skipped {
+                path,
+                reason: SkipReason::Walk(err.to_string()),
+            })
+        };
+
+        for name in [".gitignore", ".ignore"] {
+            let file = dir.join(name);
+            let Some(entry) = tree.get_name(name) else {
+                continue;
+            };
+            let blob = match self.dir.repo.find_blob(entry.id()) {
+                Ok(blob) => blob,
+                Err(err) => {
+                    skip(file, &err);
+                    continue;
+                }
+            };
+
+            let mut builder = GitignoreBuilder::new(dir);
+            for line in String::from_utf8_lossy(blob.content()).lines() {
+                if let Err(err) = builder.add_line(Some(file.clone()), line) {
+                    skip(file.clone(), &err);
+                }
+            }
+            match builder.build() {
+                Ok(gitignore) => {
+                    gitignores.matchers.push(gitignore);
+                    gitignores.ids.push(entry.id());
+                }
+                Err(err) => skip(file, &err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_stat_with_options;
+    use git2::{Signature, Time};
+    use std::fs;
+
+    /// Commits the files in the working tree of `repo` at `time` and returns the commit.
+    fn commit(repo: &Repository, message: &str, time: i64) -> Oid {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::FORCE, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::new("a", "a@example.com", &Time::new(time, 0)).unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parent.iter().collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn get_stat_at_rev_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let repo = Repository::init(root).unwrap();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write("src/a.rs", "fn a() {}\n// a\n");
+        write("src/gen/b.rs", "fn b() {}\n");
+        write("src/.hidden/c.rs", "fn c() {}\n");
+        write("vendor/d.go", "package d\n");
+        write("src/.gitignore", "gen\n");
+        write("run", "#!/usr/bin/env python3\n");
+        write("tools/h.go", "package h\n");
+        let first = commit(&repo, "first", 0);
+        write(".git/info/exclude", "/tools\n");
+
+        write("src/e.go", "package e\n");
+        commit(&repo, "second", 0);
+        // changes to the working tree don't matter
+        write("src/f.go", "package f\n");
+
+        let mut config = Config::default();
+        config.common.ignore.push("vendor".into());
+        let options = StatOptions {
+            lines: true,
+            ..Default::default()
+        };
+
+        let report = get_stat_at_rev(root, &first.to_string(), &config, &options).unwrap();
+        let langs: Vec<_> = report.stats.iter().map(|v| v.lang.as_str()).collect();
+        // b.rs is ignored by the committed .gitignore, c.rs is in a dot directory,
+        // and h.go is ignored by info/exclude
+        assert_eq!(langs, ["Python", "Rust"]);
+        assert_eq!(report.stats[1].files, 1);
+        assert_eq!(report.stats[1].comments, 1);
+
+        // the same as a checkout of the revision
+        fs::remove_file(root.join("src/e.go")).unwrap();
+        fs::remove_file(root.join("src/f.go")).unwrap();
+        let checkout = get_stat_with_options(root, &config, &options).unwrap();
+        assert_eq!(report, checkout);
+
+        let head = get_stat_at_rev(root.join("src"), "HEAD", &config, &options).unwrap();
+        let langs: Vec<_> = head.stats.iter().map(|v| v.lang.as_str()).collect();
+        assert_eq!(langs, ["Rust", "Go"]);
+
+        // a directory of the revision that was deleted from the working tree
+        fs::remove_dir_all(root.join("src")).unwrap();
+        let deleted = get_stat_at_rev(root.join("src"), "HEAD", &config, &options).unwrap();
+        assert_eq!(deleted, head);
+
+        assert!(matches!(
+            get_stat_at_rev(root, "no-such-rev", &config, &options),
+            Err(Error::Git(_))
+        ));
+    }
+
+    #[test]
+    fn get_history_test() {
+        const DAY: i64 = 24 * 60 * 60;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let repo = Repository::init(root).unwrap();
+        fs::create_dir_all(root.join("lib")).unwrap();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::write(root.join("lib/a.rs"), "fn a() {}\n").unwrap();
+
+        // one commit a day, each adding a TypeScript file
+        for day in 0..10 {
+            fs::write(root.join(format!("web/{day}.ts")), "let a = 1;\n").unwrap();
+            commit(&repo, "ts", day * DAY);
+        }
+
+        let config = Config::default();
+        let options = StatOptions::default();
+        let history = |sampling, limit| {
+            let history = HistoryOptions { sampling, limit };
+            get_history(root, "HEAD", &config, &options, &history).unwrap()
+        };
+
+        let all = history(Sampling::All, None);
+        assert_eq!(all.len(), 10);
+        assert_eq!(all[0].date, "1970-01-01");
+        assert_eq!(all[9].date, "1970-01-10");
+        // lib is unchanged and reused, and every commit matches counting it on its own
+        for point in &all {
+            let report = get_stat_at_rev(root, &point.commit, &config, &options).unwrap();
+            assert_eq!(point.stats, report.stats);
+        }
+        let typescript = |point: &HistoryPoint| {
+            point
+                .stats
+                .iter()
+                .find(|v| v.lang == "TypeScript")
+                .unwrap()
+                .files
+        };
+        assert_eq!(typescript(&all[3]), 4);
+
+        let every = history(Sampling::Every(NonZeroUsize::new(3).unwrap()), None);
+        let days: Vec<_> = every.iter().map(|v| v.time / DAY).collect();
+        assert_eq!(days, [0, 3, 6, 9]);
+
+        let weekly = history(Sampling::Weekly, None);
+        let days: Vec<_> = weekly.iter().map(|v| v.time / DAY).collect();
+        assert_eq!(days, [2, 9]);
+
+        let limited = history(Sampling::All, Some(2));
+        let days: Vec<_> = limited.iter().map(|v| v.time / DAY).collect();
+        assert_eq!(days, [8, 9]);
+    }
+
+    #[test]
+    fn date_test() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(1_706_745_599), "2024-01-31");
+        assert_eq!(date(-1), "1969-12-31");
+    }
+}
@@ -0,0 +1,167 @@
+use crate::Config;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+
+/// Compiled heuristics of a config, used to tell apart languages that share an extension.
+pub(crate) struct Heuristics<'a> {
+    /// Maps an extension to the rules for it, in config order.
+    rules: HashMap<&'a [u8], Vec<Rule>>,
+}
+
+struct Rule {
+    /// Index of the language, or `None` if the language isn't configured.
+    lang: Option<usize>,
+    /// Name of the language.
+    name: String,
+    patterns: Vec<Regex>,
+    negative_patterns: Vec<Regex>,
+}
+
+/// The rule that chose the language of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Applied<'r> {
+    /// Index of the language, or `None` if the language isn't configured.
+    pub(crate) lang: Option<usize>,
+    /// Name of the language.
+    pub(crate) name: &'r str,
+    /// The pattern that matched, or `None` if the rule has no patterns.
+    pub(crate) pattern: Option<&'r str>,
+}
+
+impl Rule {
+    /// Returns whether the rule matches `content` and, if so, the pattern that matched.
+    fn is_match(&self, content: &str) -> Option<Option<&str>> {
+        if self.negative_patterns.iter().any(|v| v.is_match(content)) {
+            return None;
+        }
+        if self.patterns.is_empty() {
+            return Some(None);
+        }
+        self.patterns
+            .iter()
+            .find(|v| v.is_match(content))
+            .map(|v| Some(v.as_str()))
+    }
+}
+
+impl<'a> Heuristics<'a> {
+    pub(crate) fn new(config: &'a Config) -> Result<Self, regex::Error> {
+        let mut rules: HashMap<&[u8], Vec<Rule>> = HashMap::new();
+
+        for heuristic in &config.heuristics {
+            for ext in &heuristic.ext {
+                let ext_rules = rules.entry(ext.as_bytes()).or_default();
+
+                for rule in &heuristic.rules {
+                    ext_rules.push(Rule {
+                        lang: config.language.iter().position(|v| v.lang == rule.lang),
+                        name: rule.lang.clone(),
+                        patterns: compile(&rule.pattern)?,
+                        negative_patterns: compile(&rule.negative_pattern)?,
+                    });
+                }
+            }
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Returns whether there are rules for `ext`.
+    pub(crate) fn contains(&self, ext: &[u8]) -> bool {
+        self.rules.contains_key(ext)
+    }
+
+    /// Returns the first rule for `ext` that matches `content`, or `None` if no rule matches.
+    /// Rules for configured languages that `applicable` rejects are skipped.
+    pub(crate) fn apply(
+        &self,
+        ext: &[u8],
+        content: &[u8],
+        applicable: &mut dyn FnMut(usize) -> bool,
+    ) -> Option<Applied<'_>> {
+        let content = String::from_utf8_lossy(content);
+
+        self.rules.get(ext)?.iter().find_map(|rule| {
+            let pattern = rule.is_match(&content)?;
+            if rule.lang.is_some_and(|index| !applicable(index)) {
+                return None;
+            }
+            Some(Applied {
+                lang: rule.lang,
+                name: &rule.name,
+                pattern,
+            })
+        })
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Regex>, regex::Error> {
+    patterns
+        .iter()
+        .map(|pattern| RegexBuilder::new(pattern).multi_line(true).build())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heuristics_test() {
+        let config: Config = r"
+language:
+  - lang: C
+    ext: [h]
+  - lang: C++
+    ext: [h]
+  - lang: Objective-C
+    ext: [h, m]
+heuristics:
+  - ext: [h]
+    rules:
+      - lang: Objective-C
+        pattern: ['^\s*@(interface|protocol)\b', '^\s*#import\b']
+      - lang: C++
+        pattern: ['^\s*template\s*<', '^\s*namespace\s+\w+']
+        negative_pattern: ['^\s*extern \x22C\x22']
+      - lang: C
+  - ext: [m]
+    rules:
+      - lang: MATLAB
+        pattern: ['^\s*function\b']
+common:
+  ignore: []
+"
+        .parse()
+        .unwrap();
+        let heuristics = Heuristics::new(&config).unwrap();
+
+        assert!(heuristics.contains(b"h"));
+        assert!(!heuristics.contains(b"c"));
+
+        let apply = |ext: &str, content: &str| {
+            heuristics
+                .apply(ext.as_bytes(), content.as_bytes(), &mut |_| true)
+                .map(|v| v.lang)
+        };
+        assert_eq!(apply("h", "@interface Foo : NSObject"), Some(Some(2)));
+        assert_eq!(apply("h", "namespace foo {\n}"), Some(Some(1)));
+        assert_eq!(
+            apply("h", "extern \"C\" {\nnamespace foo {}"),
+            Some(Some(0))
+        );
+        assert_eq!(apply("h", "int main(void);"), Some(Some(0)));
+        assert_eq!(apply("m", "function y = f(x)"), Some(None));
+        assert_eq!(apply("m", "@implementation Foo"), None);
+        assert_eq!(apply("c", "int main(void);"), None);
+
+        let applied = heuristics
+            .apply(b"h", b"namespace foo {}", &mut |_| true)
+            .unwrap();
+        assert_eq!(applied.name, "C++");
+        assert_eq!(applied.pattern, Some(r"^\s*namespace\s+\w+"));
+
+        let applied = heuristics.apply(b"h", b"namespace foo {}", &mut |index| index != 1);
+        assert_eq!(applied.map(|v| (v.lang, v.pattern)), Some((Some(0), None)));
+    }
+}
@@ -4,155 +4,682 @@
 //! ## Usage
 //!
 //! ```rust
-//! use languatage::{get_stat, LanguageStat};
+//! use languatage::{get_stat, Report};
 //!
-//! let stat: std::io::Result<Vec<LanguageStat>> = get_stat(".");
+//! let report: languatage::Result<Report> = get_stat(".");
 //! ```
 
+mod classify;
 pub mod config;
+mod diff;
+mod error;
+mod explain;
+#[cfg(feature = "git")]
+mod git;
+mod heuristics;
+mod lines;
+mod linguist;
+mod packages;
+mod patterns;
+pub mod render;
+mod sniff;
+mod walk;
 
-pub use crate::config::Config;
+#[cfg(feature = "git")]
+pub use crate::git::{get_history, get_stat_at_rev, HistoryOptions, HistoryPoint, Sampling};
+use crate::{
+    classify::{Classifier, Content},
+    config::{CommonConfig, LanguageConfigItem},
+    patterns::Patterns,
+    walk::{walk, Walked},
+};
+pub use crate::{
+    config::{Config, LanguageType},
+    diff::{diff_stats, DiffStatus, LanguageDiff},
+    error::{Error, Result, SkipReason, Skipped},
+    explain::{explain, Explanation, Reason, Rejected},
+    lines::LineCount,
+    packages::PackageKind,
+};
+use serde::Serialize;
 use std::{
-    borrow::Cow,
-    fs::{self, DirEntry},
-    path::{Path, MAIN_SEPARATOR},
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
 };
 
-#[derive(Debug, Clone, PartialEq)]
+/// Statistics of a language.
+///
+/// `lines`, `code`, `comments` and `blanks` are only counted if
+/// [`StatOptions::counts_lines`] is set, and are `0` otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct LanguageStat {
     pub lang: String,
+    /// Size of the files in bytes.
     pub size: u64,
+    /// Share of the language in percent, by [`StatOptions::metric`].
     pub percentage: f64,
+    /// Number of files.
+    pub files: u64,
+    pub lines: u64,
+    pub code: u64,
+    pub comments: u64,
+    pub blanks: u64,
+    /// Color of the language from the config, as a hex string like `#dea584`.
+    pub color: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: LanguageType,
+}
+
+/// The quantity that language percentages are computed from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Metric {
+    /// Size of the files in bytes.
+    #[default]
+    Bytes,
+    /// Number of lines.
+    Lines,
+    /// Number of lines with code.
+    Code,
+    /// Number of files.
+    Files,
+}
+
+impl Metric {
+    /// Returns the value of this metric in `stat`.
+    pub fn value(self, stat: &LanguageStat) -> u64 {
+        match self {
+            Self::Bytes => stat.size,
+            Self::Lines => stat.lines,
+            Self::Code => stat.code,
+            Self::Files => stat.files,
+        }
+    }
+
+    /// Returns whether this metric needs lines to be counted.
+    pub fn needs_lines(self) -> bool {
+        matches!(self, Self::Lines | Self::Code)
+    }
+}
+
+/// Language usage statistics of a directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    /// Statistics of the languages that were found, largest first.
+    pub stats: Vec<LanguageStat>,
+    /// Paths that were left out of the statistics because of errors, sorted by path.
+    pub skipped: Vec<Skipped>,
 }
 
 /// Returns language usage statistics.
 /// ```rust
-/// use languatage::{get_stat, LanguageStat};
+/// use languatage::{get_stat, Report};
 ///
-/// let stat: std::io::Result<Vec<LanguageStat>> = get_stat(".");
+/// let report: languatage::Result<Report> = get_stat(".");
 /// ```
-pub fn get_stat<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<LanguageStat>> {
+pub fn get_stat<P: AsRef<Path>>(path: P) -> Result<Report> {
     let config = Config::default();
     get_stat_with_config(path, &config)
 }
 
+/// Options that control how a directory is scanned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatOptions {
+    /// Number of threads used to walk the directory. `0` picks a number automatically.
+    pub threads: usize,
+    /// The quantity that language percentages are computed from.
+    pub metric: Metric,
+    /// Whether to count lines even if `metric` doesn't need them.
+    /// Counting lines reads every file that belongs to a language.
+    pub lines: bool,
+    /// Types of languages to leave out of the statistics. Their files are still matched,
+    /// so they aren't counted as another language instead.
+    /// Data and prose languages are left out by default, like linguist does.
+    pub exclude_types: Vec<LanguageType>,
+}
+
+impl Default for StatOptions {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            metric: Metric::default(),
+            lines: false,
+            exclude_types: vec![LanguageType::Data, LanguageType::Prose],
+        }
+    }
+}
+
+impl StatOptions {
+    /// Returns whether lines are counted.
+    pub fn counts_lines(&self) -> bool {
+        self.lines || self.metric.needs_lines()
+    }
+}
+
 /// Returns language usage statistics based on specified config.
 /// ```rust
-/// use languatage::{get_stat_with_config, Config, LanguageStat};
+/// use languatage::{get_stat_with_config, Config, Report};
+///
+/// let config: Config = Config::default();
+/// let report: languatage::Result<Report> = get_stat_with_config(".", &config);
+/// ```
+pub fn get_stat_with_config<P: AsRef<Path>>(path: P, config: &Config) -> Result<Report> {
+    get_stat_with_options(path, config, &StatOptions::default())
+}
+
+/// Returns language usage statistics based on specified config and options.
+///
+/// Fails if `path` can't be accessed or the config has an invalid pattern. Paths below
+/// `path` that can't be read are listed in [`Report::skipped`] instead.
+/// ```rust
+/// use languatage::{get_stat_with_options, Config, Report, StatOptions};
 ///
 /// let config: Config = Config::default();
-/// let stat: std::io::Result<Vec<LanguageStat>> = get_stat_with_config(".", &config);
+/// let options = StatOptions {
+///     threads: 4,
+///     ..Default::default()
+/// };
+/// let report: languatage::Result<Report> = get_stat_with_options(".", &config, &options);
 /// ```
-pub fn get_stat_with_config<P: AsRef<Path>>(
+pub fn get_stat_with_options<P: AsRef<Path>>(
     path: P,
     config: &Config,
-) -> std::io::Result<Vec<LanguageStat>> {
-    let sizes = get_size(path, config)?;
-    let mut sizes = sizes
-        .into_iter()
-        .filter(|(_, s)| *s != 0)
-        .collect::<Vec<_>>();
-    sizes.sort_by(|a, b| b.1.cmp(&a.1));
+    options: &StatOptions,
+) -> Result<Report> {
+    let Walked { files, skipped } = classify_files(path, config, options)?;
+    let stats = summarize(config, files.iter().map(|(_, file)| file), options);
 
-    let total_size: u64 = sizes.iter().map(|v| v.1).sum();
+    Ok(Report { stats, skipped })
+}
 
-    let result = sizes
-        .iter()
-        .map(|v| LanguageStat {
-            lang: v.0.clone(),
-            size: v.1,
-            percentage: v.1 as f64 / total_size as f64 * 100.0,
+/// A file that was counted, from [`get_files`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileStat {
+    /// Path of the file relative to the scanned directory.
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    /// The language the file is counted as, i.e. the group of its language if it has one.
+    pub lang: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Line counts of the file if [`StatOptions::counts_lines`] is set.
+    pub lines: Option<LineCount>,
+}
+
+/// The files that were counted in a directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReport {
+    /// The files that were counted, sorted by path.
+    pub files: Vec<FileStat>,
+    /// Paths that were left out of the statistics because of errors, sorted by path.
+    pub skipped: Vec<Skipped>,
+}
+
+/// Returns every file that goes into the statistics of [`get_stat_with_options`],
+/// with its language, size and line counts.
+/// ```rust
+/// use languatage::{get_files, Config, FileReport, StatOptions};
+///
+/// let config: Config = Config::default();
+/// let report: languatage::Result<FileReport> =
+///     get_files(".", &config, &StatOptions::default());
+/// ```
+pub fn get_files<P: AsRef<Path>>(
+    path: P,
+    config: &Config,
+    options: &StatOptions,
+) -> Result<FileReport> {
+    let root = path.as_ref();
+    let Walked { files, skipped } = classify_files(root, config, options)?;
+    let Slots { stats, of_lang } = Slots::new(config);
+
+    let files = files
+        .into_iter()
+        .filter_map(|(path, file)| {
+            let stat = &stats[of_lang[file.lang]];
+            if options.exclude_types.contains(&stat.kind) {
+                return None;
+            }
+            Some(FileStat {
+                path: relative_path(root, path),
+                lang: stat.lang.clone(),
+                size: file.size,
+                lines: file.lines,
+            })
         })
         .collect();
 
-    Ok(result)
+    Ok(FileReport { files, skipped })
 }
 
-fn get_size<P: AsRef<Path>>(path: P, config: &Config) -> std::io::Result<Vec<(String, u64)>> {
-    let common_ignores = &config.common.ignore;
+/// Statistics of a directory and the files below it, from [`get_tree`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DirStat {
+    /// Path of the directory relative to the scanned directory, empty for the scanned directory.
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    /// Statistics of all files below the directory, largest first, with percentages
+    /// of the directory.
+    pub stats: Vec<LanguageStat>,
+    /// Subdirectories with counted files, sorted by path. Empty at the depth limit.
+    pub children: Vec<DirStat>,
+}
+
+/// Statistics of a directory tree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeReport {
+    /// Statistics of the scanned directory, with its subdirectories as children.
+    pub root: DirStat,
+    /// Paths that were left out of the statistics because of errors, sorted by path.
+    pub skipped: Vec<Skipped>,
+}
 
-    let result = config
-        .language
+/// Returns the statistics of [`get_stat_with_options`] for the scanned directory and,
+/// down to `depth` levels, each subdirectory. A `depth` of `0` only returns the
+/// scanned directory.
+/// ```rust
+/// use languatage::{get_tree, Config, StatOptions, TreeReport};
+///
+/// let config: Config = Config::default();
+/// let report: languatage::Result<TreeReport> =
+///     get_tree(".", &config, &StatOptions::default(), 2);
+/// ```
+pub fn get_tree<P: AsRef<Path>>(
+    path: P,
+    config: &Config,
+    options: &StatOptions,
+    depth: usize,
+) -> Result<TreeReport> {
+    let root = path.as_ref();
+    let Walked { files, skipped } = classify_files(root, config, options)?;
+    let files = files
         .iter()
-        .filter_map(|language| {
-            // concat common_ignores and lang_ignores
-            let ignores: Vec<_> = common_ignores
-                .iter()
-                .chain(language.ignore.iter())
-                .collect();
+        .map(|(path, file)| (path.strip_prefix(root).unwrap_or(path), file))
+        .collect();
 
-            let entries = &get_dir_entries(&path, &ignores, &language.ext)?;
+    Ok(TreeReport {
+        root: dir_stat(config, options, PathBuf::new(), files, depth),
+        skipped,
+    })
+}
 
-            let size: u64 = entries
-                .iter()
-                .filter_map(|v| Some(v.metadata().ok()?.len()))
-                .sum();
+/// Sums up `files`, given by their paths relative to the directory at `path`,
+/// and recurses into the subdirectories they are in.
+fn dir_stat(
+    config: &Config,
+    options: &StatOptions,
+    path: PathBuf,
+    files: Vec<(&Path, &ClassifiedFile)>,
+    depth: usize,
+) -> DirStat {
+    let stats = summarize(config, files.iter().map(|(_, file)| *file), options);
+    if depth == 0 {
+        return DirStat {
+            path,
+            stats,
+            children: vec![],
+        };
+    }
 
-            Some((language.lang.clone(), size))
-        })
+    let mut subdirs: BTreeMap<&OsStr, Vec<_>> = BTreeMap::new();
+    for (relative, file) in files {
+        let mut components = relative.components();
+        if let (Some(dir), rest) = (components.next(), components.as_path()) {
+            if !rest.as_os_str().is_empty() {
+                subdirs
+                    .entry(dir.as_os_str())
+                    .or_default()
+                    .push((rest, file));
+            }
+        }
+    }
+
+    let children = subdirs
+        .into_iter()
+        .map(|(name, files)| dir_stat(config, options, path.join(name), files, depth - 1))
+        .filter(|child| !child.stats.is_empty())
         .collect();
 
-    Ok(result)
+    DirStat {
+        path,
+        stats,
+        children,
+    }
+}
+
+/// Statistics of a package, from [`get_packages`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackageStat {
+    /// Path of the package directory relative to the scanned directory, empty for the
+    /// scanned directory.
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    /// Name of the package from its manifests, if they have one.
+    pub name: Option<String>,
+    /// Kinds of the manifests in the package directory, in order.
+    pub kinds: Vec<PackageKind>,
+    /// Statistics of the files of the package, largest first, with percentages of the package.
+    pub stats: Vec<LanguageStat>,
+}
+
+/// Statistics of a directory and of each package in it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackageReport {
+    /// Statistics of all files, like [`Report::stats`].
+    pub stats: Vec<LanguageStat>,
+    /// The packages that were found, sorted by path.
+    pub packages: Vec<PackageStat>,
+    /// Paths that were left out of the statistics because of errors, sorted by path.
+    pub skipped: Vec<Skipped>,
 }
 
-/// Returns all files under the given path that match the common config
-fn get_dir_entries<
-    'a,
-    P: AsRef<Path>,
-    S: Into<Cow<'a, str>> + std::fmt::Display,
-    X: Into<Cow<'a, str>> + std::fmt::Display,
->(
+/// Returns the statistics of [`get_stat_with_options`] together with the statistics
+/// of each package in the directory.
+///
+/// A directory is a package if it has a manifest like `Cargo.toml`, `package.json`,
+/// `go.mod` or `pyproject.toml` (see [`PackageKind`]), so workspace roots and their
+/// members are all packages. A file belongs to the innermost package it is in, and files
+/// outside of every package only count towards [`PackageReport::stats`].
+/// ```rust
+/// use languatage::{get_packages, Config, PackageReport, StatOptions};
+///
+/// let config: Config = Config::default();
+/// let report: languatage::Result<PackageReport> =
+///     get_packages(".", &config, &StatOptions::default());
+/// ```
+pub fn get_packages<P: AsRef<Path>>(
     path: P,
-    ignores: &[S],
-    exts: &[X],
-) -> Option<Vec<DirEntry>> {
-    let path = path.as_ref().to_str()?;
+    config: &Config,
+    options: &StatOptions,
+) -> Result<PackageReport> {
+    let root = path.as_ref();
+    check_root(root)?;
+
+    // manifests are found where `common.include` leaves files out as well, so the walk
+    // visits every file and checks `common.include` itself
+    let classifier = Classifier::new(root, config)?;
+    let include = Patterns::new(root, &config.common.include).map_err(Error::invalid_pattern)?;
+    let common = CommonConfig {
+        include: vec![],
+        ..config.common.clone()
+    };
+    let walked = walk(root, &common, options.threads, |entry| {
+        let manifest = packages::read_manifest(entry.path());
+        let included = include.is_empty() || include.is_match_or_any_parents(entry.path(), false);
+        let file = match included {
+            true => classify_entry(&classifier, config, options, entry)?,
+            false => None,
+        };
+        Ok((manifest.is_some() || file.is_some()).then_some((file, manifest)))
+    })?;
+
+    let mut packages: BTreeMap<PathBuf, (Option<String>, Vec<PackageKind>)> = BTreeMap::new();
+    let mut files = vec![];
+    for (path, (file, manifest)) in walked.files {
+        if let (Some(manifest), Some(dir)) = (manifest, path.parent()) {
+            let (name, kinds) = packages.entry(dir.to_path_buf()).or_default();
+            if name.is_none() {
+                *name = manifest.name;
+            }
+            if !kinds.contains(&manifest.kind) {
+                kinds.push(manifest.kind);
+            }
+        }
+        if let Some(file) = file {
+            files.push((path, file));
+        }
+    }
+    let stats = summarize(config, files.iter().map(|(_, file)| file), options);
 
-    let is_dot_dir = path != "." && path.split(&['/', '\\'][..]).last()?.starts_with('.');
+    let of_dir: HashMap<&Path, usize> = packages
+        .keys()
+        .enumerate()
+        .map(|(i, dir)| (dir.as_path(), i))
+        .collect();
+    let mut files_of: Vec<Vec<&ClassifiedFile>> = vec![vec![]; packages.len()];
+    for (path, file) in &files {
+        if let Some(&i) = path.ancestors().skip(1).find_map(|dir| of_dir.get(dir)) {
+            files_of[i].push(file);
+        }
+    }
 
-    if is_dot_dir {
-        return None;
+    let packages = packages
+        .iter()
+        .zip(files_of)
+        .map(|((dir, (name, kinds)), files)| PackageStat {
+            path: dir.strip_prefix(root).unwrap_or(dir).to_path_buf(),
+            name: name.clone(),
+            kinds: kinds.clone(),
+            stats: summarize(config, files, options),
+        })
+        .collect();
+
+    Ok(PackageReport {
+        stats,
+        packages,
+        skipped: walked.skipped,
+    })
+}
+
+/// Returns `path` relative to `root`, or `path` itself if `root` is the file itself.
+fn relative_path(root: &Path, path: PathBuf) -> PathBuf {
+    match path.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.to_path_buf(),
+        _ => path,
     }
+}
+
+/// Serializes a path lossily, so that non-UTF-8 paths don't fail.
+pub(crate) fn serialize_path<S: serde::Serializer>(
+    path: &Path,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(&path.display())
+}
+
+/// A file that belongs to a language.
+pub(crate) struct ClassifiedFile {
+    /// Index of the language in the config.
+    pub(crate) lang: usize,
+    pub(crate) size: u64,
+    pub(crate) lines: Option<LineCount>,
+}
+
+/// Returns the files under `path` that belong to a language.
+fn classify_files<P: AsRef<Path>>(
+    path: P,
+    config: &Config,
+    options: &StatOptions,
+) -> Result<Walked<ClassifiedFile>> {
+    let path = path.as_ref();
+    check_root(path)?;
+
+    let classifier = Classifier::new(path, config)?;
+    walk(path, &config.common, options.threads, |entry| {
+        classify_entry(&classifier, config, options, entry)
+    })
+}
+
+/// Fails with [`Error::Io`] if the scanned path `root` can't be read.
+fn check_root(root: &Path) -> Result<()> {
+    fs::metadata(root).map_err(|source| Error::Io {
+        path: root.to_path_buf(),
+        source,
+    })?;
+    Ok(())
+}
+
+/// Classifies a file found by [`walk`], see [`classify_file`].
+fn classify_entry(
+    classifier: &Classifier,
+    config: &Config,
+    options: &StatOptions,
+    entry: &ignore::DirEntry,
+) -> Result<Option<ClassifiedFile>, SkipReason> {
+    let size = entry
+        .metadata()
+        .map_err(|err| SkipReason::Metadata(err.to_string()))?
+        .len();
+    let content = Content::file(entry.path());
+    classify_file(
+        classifier,
+        config,
+        options.counts_lines(),
+        entry.path(),
+        size,
+        &content,
+    )
+}
 
-    let read_dir = match fs::read_dir(path) {
-        Ok(read_dir) => read_dir,
-        Err(_) => return None,
+/// Returns the language of the file at `path` and, if `counts_lines` is set, its line counts,
+/// or `None` if it doesn't belong to a language.
+pub(crate) fn classify_file(
+    classifier: &Classifier,
+    config: &Config,
+    counts_lines: bool,
+    path: &Path,
+    size: u64,
+    content: &Content,
+) -> Result<Option<ClassifiedFile>, SkipReason> {
+    let read_error = || match content.error() {
+        Some(err) => Err(SkipReason::Read(err.to_string())),
+        None => Ok(None),
     };
 
-    let result = read_dir
-        .into_iter()
-        .filter_map(|entry| -> Option<Vec<DirEntry>> {
-            let entry = entry.ok()?;
+    let lang = match classifier.classify(path, size, content) {
+        Some(lang) => lang,
+        None => return read_error(),
+    };
 
-            let entry_path = entry.path();
-            let entry_path = entry_path.to_string_lossy();
+    let lines = match counts_lines {
+        true => match content.get() {
+            Some(content) => Some(lines::count_lines(content, &config.language[lang])),
+            None => return read_error(),
+        },
+        false => None,
+    };
 
-            let is_ignored = ignores.iter().any(|ignore| {
-                entry_path.contains(&format!("{MAIN_SEPARATOR}{ignore}{MAIN_SEPARATOR}"))
-            });
+    Ok(Some(ClassifiedFile { lang, size, lines }))
+}
 
-            if is_ignored {
-                return None;
-            };
+/// Empty statistics of the languages of a config, with languages of a group merged into one.
+struct Slots {
+    stats: Vec<LanguageStat>,
+    /// Index in `stats` of each language in the config.
+    of_lang: Vec<usize>,
+}
 
-            if entry.metadata().ok()?.is_dir() {
-                return get_dir_entries(entry.path(), ignores, exts);
-            };
+impl Slots {
+    fn new(config: &Config) -> Self {
+        let mut stats: Vec<LanguageStat> = vec![];
+        let mut slots = HashMap::new();
+        let mut slot = |name: &str, language: &LanguageConfigItem| {
+            *slots.entry(name.to_string()).or_insert_with(|| {
+                stats.push(LanguageStat {
+                    lang: name.to_string(),
+                    color: language.color.clone(),
+                    kind: language.kind,
+                    ..Default::default()
+                });
+                stats.len() - 1
+            })
+        };
 
-            let is_correct_ext = exts
-                .iter()
-                .any(|ext| entry_path.ends_with(&format!(".{ext}")));
+        // languages named by a group come first, so that the group takes their color and type
+        for language in &config.language {
+            slot(&language.lang, language);
+        }
+        let of_lang = config
+            .language
+            .iter()
+            .map(|language| slot(language.counted_as(), language))
+            .collect();
 
-            if is_correct_ext {
-                Some(vec![entry])
-            } else {
-                None
-            }
-        })
-        .flatten()
-        .collect();
+        Self { stats, of_lang }
+    }
+}
+
+/// Sums of the files of a language.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Sums {
+    pub(crate) files: u64,
+    pub(crate) size: u64,
+    pub(crate) lines: LineCount,
+}
+
+impl Sums {
+    pub(crate) fn of(file: &ClassifiedFile) -> Self {
+        Self {
+            files: 1,
+            size: file.size,
+            lines: file.lines.unwrap_or_default(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, other: &Self) {
+        self.files += other.files;
+        self.size += other.size;
+        self.lines.lines += other.lines.lines;
+        self.lines.code += other.lines.code;
+        self.lines.comments += other.lines.comments;
+        self.lines.blanks += other.lines.blanks;
+    }
+}
+
+/// Sums up `files` by language, counting languages with a `group` as the group,
+/// and computes the percentages by [`StatOptions::metric`].
+/// Languages without any of the metric or with one of [`StatOptions::exclude_types`]
+/// are left out, and the rest are sorted by the metric.
+pub(crate) fn summarize<'a, I>(
+    config: &Config,
+    files: I,
+    options: &StatOptions,
+) -> Vec<LanguageStat>
+where
+    I: IntoIterator<Item = &'a ClassifiedFile>,
+{
+    let sums = files.into_iter().map(|file| (file.lang, Sums::of(file)));
+    summarize_sums(config, sums, options)
+}
+
+/// Like [`summarize`], but with the sums of files by the index of their language.
+pub(crate) fn summarize_sums<I>(
+    config: &Config,
+    sums: I,
+    options: &StatOptions,
+) -> Vec<LanguageStat>
+where
+    I: IntoIterator<Item = (usize, Sums)>,
+{
+    let metric = options.metric;
+    let Slots { mut stats, of_lang } = Slots::new(config);
 
-    Some(result)
+    let mut totals = vec![Sums::default(); stats.len()];
+    for (lang, sums) in sums {
+        totals[of_lang[lang]].add(&sums);
+    }
+    for (stat, total) in stats.iter_mut().zip(totals) {
+        stat.files = total.files;
+        stat.size = total.size;
+        stat.lines = total.lines.lines;
+        stat.code = total.lines.code;
+        stat.comments = total.lines.comments;
+        stat.blanks = total.lines.blanks;
+    }
+
+    stats.retain(|stat| metric.value(stat) != 0 && !options.exclude_types.contains(&stat.kind));
+    stats.sort_by_key(|stat| Reverse(metric.value(stat)));
+
+    let total: u64 = stats.iter().map(|stat| metric.value(stat)).sum();
+    for stat in &mut stats {
+        stat.percentage = metric.value(stat) as f64 / total as f64 * 100.0;
+    }
+
+    stats
 }
 
 #[cfg(test)]
@@ -160,7 +687,7 @@ mod tests {
     use super::*;
     #[test]
     fn test_get_stat() {
-        let stat = get_stat(".").unwrap();
+        let stat = get_stat(".").unwrap().stats;
 
         assert_eq!(stat[0].lang, "Rust".to_string());
         assert_eq!(stat[0].percentage, 100.0);
@@ -169,7 +696,7 @@ mod tests {
     #[test]
     fn test_get_stat_with_config() {
         let config = Config::default();
-        let stat = get_stat_with_config(".", &config).unwrap();
+        let stat = get_stat_with_config(".", &config).unwrap().stats;
 
         assert_eq!(stat[0].lang, "Rust".to_string());
         assert_eq!(stat[0].percentage, 100.0);
@@ -177,32 +704,329 @@ mod tests {
     }
 
     #[test]
-    fn test_get_dir_entries() {
+    fn test_get_stat_with_options() {
         let config = Config::default();
-        let common_ignores = &config.common.ignore;
-        let lang_ignores = &config.language[0].ignore;
-        let ignores: Vec<_> = common_ignores.iter().chain(lang_ignores.iter()).collect();
+        let options = |threads| StatOptions {
+            threads,
+            lines: true,
+            ..Default::default()
+        };
+        let serial = get_stat_with_options(".", &config, &options(1)).unwrap();
+        let parallel = get_stat_with_options(".", &config, &options(8)).unwrap();
+
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn test_get_stat_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "// a\nfn a() {}\n\nfn b() {}\n").unwrap();
+        std::fs::write(dir.path().join("b.go"), "package b\n").unwrap();
+        std::fs::write(dir.path().join("c.go"), "// c\n").unwrap();
+
+        let config = Config::default();
+        let stat = |metric| {
+            let options = StatOptions {
+                metric,
+                ..Default::default()
+            };
+            get_stat_with_options(dir.path(), &config, &options)
+                .unwrap()
+                .stats
+        };
+
+        let bytes = stat(Metric::Bytes);
+        assert_eq!(bytes[0].lang, "Rust");
+        assert_eq!(bytes[0].size, 26);
+        assert_eq!(bytes[0].lines, 0);
 
+        let files = stat(Metric::Files);
+        assert_eq!(files[0].lang, "Go");
+        assert_eq!(files[0].files, 2);
+        assert!((files[0].percentage - 200.0 / 3.0).abs() < 1e-9);
+
+        let code = stat(Metric::Code);
+        assert_eq!(code[0].lang, "Rust");
         assert_eq!(
-            get_dir_entries(".", common_ignores, &config.language[0].ext)
+            (
+                code[0].lines,
+                code[0].code,
+                code[0].comments,
+                code[0].blanks
+            ),
+            (4, 2, 1, 1)
+        );
+        assert_eq!((code[1].lines, code[1].code, code[1].comments), (2, 1, 1));
+        assert_eq!(code[0].percentage, 2.0 / 3.0 * 100.0);
+
+        let lines = stat(Metric::Lines);
+        assert_eq!(lines[0].percentage, 4.0 / 6.0 * 100.0);
+    }
+
+    #[test]
+    fn test_get_stat_exclude_types() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+        std::fs::write(dir.path().join("b.vue"), "<template></template>").unwrap();
+
+        let config = Config::default();
+        let stat = |exclude_types| {
+            let options = StatOptions {
+                exclude_types,
+                ..Default::default()
+            };
+            get_stat_with_options(dir.path(), &config, &options)
                 .unwrap()
-                .iter()
-                .any(|entry| entry
-                    .path()
-                    .to_string_lossy()
-                    .contains(&format!("{}.git{}", MAIN_SEPARATOR, MAIN_SEPARATOR))),
-            false
+                .stats
+        };
+
+        let all = stat(vec![]);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].kind, LanguageType::Markup);
+        assert_eq!(all[0].color.as_deref(), Some("#41b883"));
+
+        let programming = stat(vec![LanguageType::Markup]);
+        assert_eq!(programming.len(), 1);
+        assert_eq!(programming[0].lang, "Rust");
+        assert_eq!(programming[0].percentage, 100.0);
+    }
+
+    #[test]
+    fn test_get_stat_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.ts"), "let a = 1;").unwrap();
+        std::fs::write(dir.path().join("b.tsx"), "let b = <b />;").unwrap();
+
+        let config: Config = "
+language:
+  - lang: TSX
+    color: '#000000'
+    group: TypeScript
+    ext: [tsx]
+  - lang: TypeScript
+    color: '#3178c6'
+    ext: [ts]
+common:
+  ignore: []
+"
+        .parse()
+        .unwrap();
+        let stat = get_stat_with_config(dir.path(), &config).unwrap().stats;
+
+        assert_eq!(stat.len(), 1);
+        assert_eq!(stat[0].lang, "TypeScript");
+        assert_eq!(stat[0].color.as_deref(), Some("#3178c6"));
+        assert_eq!((stat[0].files, stat[0].size), (2, 24));
+    }
+
+    #[test]
+    fn test_get_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/a.rs"), "fn a() {}\n// a\n").unwrap();
+        std::fs::write(dir.path().join("b.go"), "package b\n").unwrap();
+        std::fs::write(dir.path().join("c.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("d.txt"), "d").unwrap();
+
+        let config = Config::default();
+        let options = StatOptions {
+            lines: true,
+            exclude_types: vec![LanguageType::Data],
+            ..Default::default()
+        };
+        let report = get_files(dir.path(), &config, &options).unwrap();
+
+        assert_eq!(
+            report.files,
+            [
+                FileStat {
+                    path: "b.go".into(),
+                    lang: "Go".into(),
+                    size: 10,
+                    lines: Some(LineCount {
+                        lines: 1,
+                        code: 1,
+                        comments: 0,
+                        blanks: 0
+                    }),
+                },
+                FileStat {
+                    path: PathBuf::from("src").join("a.rs"),
+                    lang: "Rust".into(),
+                    size: 15,
+                    lines: Some(LineCount {
+                        lines: 2,
+                        code: 1,
+                        comments: 1,
+                        blanks: 0
+                    }),
+                },
+            ]
         );
 
+        let stats = get_stat_with_options(dir.path(), &config, &options)
+            .unwrap()
+            .stats;
         assert_eq!(
-            get_dir_entries(".", &ignores, &config.language[0].ext)
-                .unwrap()
-                .iter()
-                .any(|entry| entry
-                    .path()
-                    .to_string_lossy()
-                    .contains(&format!("{}.git{}", MAIN_SEPARATOR, MAIN_SEPARATOR))),
-            false
+            stats.iter().map(|v| v.size).sum::<u64>(),
+            report.files.iter().map(|v| v.size).sum::<u64>()
+        );
+
+        let file = get_files(dir.path().join("b.go"), &config, &options).unwrap();
+        assert_eq!(file.files[0].path, dir.path().join("b.go"));
+    }
+
+    #[test]
+    fn test_get_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("app/src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("app/src/a.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(dir.path().join("app/b.go"), "package b\n").unwrap();
+        std::fs::write(dir.path().join("docs/c.txt"), "c").unwrap();
+        std::fs::write(dir.path().join("d.go"), "package d\n").unwrap();
+
+        let config = Config::default();
+        let report = get_tree(dir.path(), &config, &StatOptions::default(), 1).unwrap();
+        let langs =
+            |dir: &DirStat| -> Vec<_> { dir.stats.iter().map(|v| v.lang.clone()).collect() };
+
+        let root = &report.root;
+        assert_eq!(root.path, PathBuf::new());
+        assert_eq!(langs(root), ["Go", "Rust"]);
+        // docs has no counted files
+        assert_eq!(root.children.len(), 1);
+
+        let app = &root.children[0];
+        assert_eq!(app.path, PathBuf::from("app"));
+        assert_eq!(langs(app), ["Rust", "Go"]);
+        assert_eq!(app.stats[0].percentage, 10.0 / 20.0 * 100.0);
+        assert!(app.children.is_empty());
+
+        let report = get_tree(dir.path(), &config, &StatOptions::default(), 2).unwrap();
+        let src = &report.root.children[0].children[0];
+        assert_eq!(src.path, PathBuf::from("app").join("src"));
+        assert_eq!(langs(src), ["Rust"]);
+    }
+
+    #[test]
+    fn test_get_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("Cargo.toml", "[workspace]\nmembers = [\"crates/app\"]\n");
+        write("build.rs", "fn main() {}\n");
+        write("crates/app/Cargo.toml", "[package]\nname = \"app\"\n");
+        write("crates/app/src/main.rs", "fn main() {}\n");
+        write("web/package.json", r#"{ "name": "web" }"#);
+        write("web/src/a.ts", "export const a = 1;\n");
+        write("web/src/b.js", "b\n");
+        write("web/node_modules/c/package.json", r#"{ "name": "c" }"#);
+        write("docs/d.rs", "fn d() {}\n");
+        write("tools/pyproject.toml", "[project]\nname = \"tools\"\n");
+        write("tools/setup.py", "");
+
+        let config = Config::default();
+        let options = StatOptions {
+            metric: Metric::Files,
+            exclude_types: vec![],
+            ..Default::default()
+        };
+        let report = get_packages(dir.path(), &config, &options).unwrap();
+        let paths: Vec<_> = report.packages.iter().map(|v| v.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::new(),
+                PathBuf::from("crates").join("app"),
+                PathBuf::from("tools"),
+                PathBuf::from("web")
+            ]
+        );
+
+        // the workspace root has no name, and keeps the files outside of its members
+        let root = &report.packages[0];
+        assert_eq!(
+            (root.name.as_deref(), &root.kinds[..]),
+            (None, &[PackageKind::Cargo][..])
         );
+        assert_eq!(root.stats[0].files, 2);
+
+        let app = &report.packages[1];
+        assert_eq!(app.name.as_deref(), Some("app"));
+        assert_eq!(app.stats[0].lang, "Rust");
+        assert_eq!(app.stats[1].lang, "TOML");
+
+        // a package with two manifests of the same kind has the kind once
+        let tools = &report.packages[2];
+        assert_eq!(tools.kinds, [PackageKind::Python]);
+
+        let web = &report.packages[3];
+        assert_eq!(web.name.as_deref(), Some("web"));
+        let typescript = web.stats.iter().find(|v| v.lang == "TypeScript").unwrap();
+        assert_eq!(typescript.percentage, 1.0 / 3.0 * 100.0);
+        assert_eq!(web.stats.len(), 3);
+
+        let total: u64 = report.stats.iter().map(|v| v.files).sum();
+        assert_eq!(total, 10);
+    }
+
+    #[test]
+    fn test_get_packages_include() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("web")).unwrap();
+        std::fs::write(dir.path().join("web/package.json"), r#"{ "name": "web" }"#).unwrap();
+        std::fs::write(dir.path().join("web/a.ts"), "export const a = 1;\n").unwrap();
+        std::fs::write(dir.path().join("web/b.js"), "b\n").unwrap();
+
+        let mut config = Config::default();
+        config.common.include = vec!["*.ts".into()];
+        let report = get_packages(dir.path(), &config, &StatOptions::default()).unwrap();
+
+        assert_eq!(report.packages.len(), 1);
+        assert_eq!(report.packages[0].name.as_deref(), Some("web"));
+        assert_eq!(report.packages[0].stats.len(), 1);
+        assert_eq!(report.packages[0].stats[0].lang, "TypeScript");
+        assert_eq!(report.stats, report.packages[0].stats);
+    }
+
+    #[test]
+    fn test_get_stat_errors() {
+        assert!(matches!(get_stat("./no-such-dir"), Err(Error::Io { .. })));
+
+        let mut config = Config::default();
+        config.common.ignore.push("{a,b".into());
+        assert!(matches!(
+            get_stat_with_config(".", &config),
+            Err(Error::InvalidPattern(_))
+        ));
+
+        assert!(get_stat(".").unwrap().skipped.is_empty());
+    }
+
+    #[test]
+    fn test_get_stat_counts_files_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.h"), "int a;").unwrap();
+        std::fs::write(dir.path().join("b.cpp"), "int b;").unwrap();
+
+        let config: Config = "
+language:
+  - lang: C
+    ext: [h]
+  - lang: C++
+    ext: [cpp, h]
+common:
+  ignore: []
+"
+        .parse()
+        .unwrap();
+        let stat = get_stat_with_config(dir.path(), &config).unwrap().stats;
+
+        assert_eq!(stat.iter().map(|v| v.size).sum::<u64>(), 12);
+        assert_eq!(stat.iter().map(|v| v.percentage).sum::<f64>(), 100.0);
     }
 }
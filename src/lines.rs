@@ -0,0 +1,198 @@
+use crate::config::LanguageConfigItem;
+use serde::Serialize;
+
+/// Line counts of a file or language.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LineCount {
+    pub lines: u64,
+    /// Lines with code, including lines with both code and a comment.
+    pub code: u64,
+    /// Lines with only comments.
+    pub comments: u64,
+    /// Lines with only whitespace.
+    pub blanks: u64,
+}
+
+/// Counts the lines of `content` using the comment syntax of `language`.
+pub(crate) fn count_lines(content: &[u8], language: &LanguageConfigItem) -> LineCount {
+    let mut count = LineCount::default();
+    let mut state = State::default();
+
+    for line in content.split(|&b| b == b'\n') {
+        count.lines += 1;
+
+        if line.iter().all(u8::is_ascii_whitespace) {
+            count.blanks += 1;
+            continue;
+        }
+
+        let (code, comment) = state.scan(line, language);
+        if code {
+            count.code += 1;
+        } else if comment {
+            count.comments += 1;
+        } else {
+            count.blanks += 1;
+        }
+    }
+
+    // a trailing newline doesn't start another line
+    if content.ends_with(b"\n") || content.is_empty() {
+        count.lines -= 1;
+        count.blanks -= 1;
+    }
+
+    count
+}
+
+/// What the scanner is inside of at the start of a line.
+#[derive(Default)]
+struct State {
+    /// Index of the `quotes` entry of the open string.
+    string: Option<usize>,
+    /// Index of the `block_comment` entry of the open comment and its nesting depth.
+    comment: Option<(usize, usize)>,
+}
+
+impl State {
+    /// Scans `line` and returns whether it has code and whether it has a comment.
+    fn scan(&mut self, line: &[u8], language: &LanguageConfigItem) -> (bool, bool) {
+        let mut code = false;
+        let mut comment = self.comment.is_some();
+        let mut i = 0;
+
+        while i < line.len() {
+            let rest = &line[i..];
+
+            if let Some(quote) = self.string {
+                code = true;
+                let end = language.quotes[quote].1.as_bytes();
+                if rest[0] == b'\\' {
+                    i += 2;
+                } else if rest.starts_with(end) {
+                    self.string = None;
+                    i += end.len();
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+
+            if let Some((block, depth)) = self.comment {
+                let (start, end) = &language.block_comment[block];
+                if rest.starts_with(end.as_bytes()) {
+                    self.comment = (depth > 1).then_some((block, depth - 1));
+                    i += end.len();
+                } else if language.nested_comments && rest.starts_with(start.as_bytes()) {
+                    self.comment = Some((block, depth + 1));
+                    i += start.len();
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+
+            if language
+                .line_comment
+                .iter()
+                .any(|v| rest.starts_with(v.as_bytes()))
+            {
+                return (code, true);
+            }
+
+            if let Some(block) = find_start(rest, &language.block_comment) {
+                comment = true;
+                self.comment = Some((block, 1));
+                i += language.block_comment[block].0.len();
+                continue;
+            }
+
+            if let Some(quote) = find_start(rest, &language.quotes) {
+                code = true;
+                self.string = Some(quote);
+                i += language.quotes[quote].0.len();
+                continue;
+            }
+
+            code |= !rest[0].is_ascii_whitespace();
+            i += 1;
+        }
+
+        (code, comment)
+    }
+}
+
+/// Returns the index of the first pair whose start `rest` starts with.
+fn find_start(rest: &[u8], pairs: &[(String, String)]) -> Option<usize> {
+    pairs
+        .iter()
+        .position(|(start, _)| !start.is_empty() && rest.starts_with(start.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust() -> LanguageConfigItem {
+        LanguageConfigItem {
+            lang: "Rust".into(),
+            line_comment: vec!["//".into()],
+            block_comment: vec![("/*".into(), "*/".into())],
+            nested_comments: true,
+            quotes: vec![("\"".into(), "\"".into())],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn count_lines_test() {
+        let content = r#"// comment
+fn main() { // trailing comment
+
+    let s = "/* not a comment";
+    /* block
+       /* nested */
+    still comment */
+    let t = "escaped \" // quote"; /*
+    */
+}
+"#;
+        assert_eq!(
+            count_lines(content.as_bytes(), &rust()),
+            LineCount {
+                lines: 10,
+                code: 4,
+                comments: 5,
+                blanks: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn count_lines_edge_test() {
+        let plain = LanguageConfigItem::default();
+
+        assert_eq!(count_lines(b"", &plain), LineCount::default());
+        assert_eq!(
+            count_lines(b"a\n\n  b", &plain),
+            LineCount {
+                lines: 3,
+                code: 2,
+                comments: 0,
+                blanks: 1,
+            }
+        );
+
+        let mut unnested = rust();
+        unnested.nested_comments = false;
+        assert_eq!(
+            count_lines(b"/* /* */\ncode */", &unnested),
+            LineCount {
+                lines: 2,
+                code: 1,
+                comments: 1,
+                blanks: 0,
+            }
+        );
+    }
+}
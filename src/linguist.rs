@@ -0,0 +1,149 @@
+use crate::config::{LanguageConfigItem, LanguageType};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A language in linguist's `languages.yml`. Fields that languatage has no use for,
+/// like `tm_scope` and `language_id`, are ignored.
+#[derive(Debug, Deserialize)]
+struct LinguistLanguage {
+    #[serde(rename = "type")]
+    kind: LanguageType,
+    color: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    group: Option<String>,
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default)]
+    filenames: Vec<String>,
+    #[serde(default)]
+    interpreters: Vec<String>,
+}
+
+/// Converts the languages of linguist's `languages.yml`, in file order.
+///
+/// An extension that is the primary (first) extension of some languages is dropped
+/// from the other languages that list it, since linguist relies on its heuristics and
+/// classifier to tell those apart.
+pub(crate) fn convert(s: &str) -> Result<Vec<LanguageConfigItem>, serde_yaml::Error> {
+    // a mapping, so that languages keep the order of the file
+    let languages: serde_yaml::Mapping = serde_yaml::from_str(s)?;
+    let languages = languages
+        .into_iter()
+        .map(|(name, language)| {
+            Ok((
+                serde_yaml::from_value::<String>(name)?,
+                serde_yaml::from_value::<LinguistLanguage>(language)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, serde_yaml::Error>>()?;
+
+    let mut primary: HashMap<String, Vec<&str>> = HashMap::new();
+    for (name, language) in &languages {
+        if let Some(ext) = language.extensions.first() {
+            primary.entry(strip_dot(ext)).or_default().push(name);
+        }
+    }
+
+    let items = languages
+        .iter()
+        .map(|(name, language)| {
+            let ext = language
+                .extensions
+                .iter()
+                .map(|ext| strip_dot(ext))
+                .filter(|ext| match primary.get(ext) {
+                    Some(langs) => langs.contains(&name.as_str()),
+                    None => true,
+                })
+                .collect();
+
+            LanguageConfigItem {
+                lang: name.clone(),
+                color: language.color.clone(),
+                kind: language.kind,
+                aliases: language.aliases.clone(),
+                group: language.group.clone(),
+                ext,
+                filenames: language.filenames.clone(),
+                interpreters: language.interpreters.clone(),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    Ok(items)
+}
+
+fn strip_dot(ext: &str) -> String {
+    ext.strip_prefix('.').unwrap_or(ext).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_test() {
+        let languages = convert(
+            r##"
+C:
+  type: programming
+  color: "#555555"
+  extensions:
+  - ".c"
+  - ".h"
+  interpreters:
+  - tcc
+  tm_scope: source.c
+  language_id: 41
+Objective-C:
+  type: programming
+  color: "#438eff"
+  aliases:
+  - objc
+  extensions:
+  - ".m"
+  - ".h"
+MATLAB:
+  type: programming
+  extensions:
+  - ".matlab"
+  - ".m"
+TSX:
+  type: programming
+  group: TypeScript
+  extensions:
+  - ".tsx"
+Dockerfile:
+  type: programming
+  extensions:
+  - ".dockerfile"
+  filenames:
+  - Dockerfile
+"##,
+        )
+        .unwrap();
+
+        let names: Vec<_> = languages.iter().map(|v| v.lang.as_str()).collect();
+        assert_eq!(names, ["C", "Objective-C", "MATLAB", "TSX", "Dockerfile"]);
+        assert_eq!(
+            languages[0],
+            LanguageConfigItem {
+                lang: "C".into(),
+                color: Some("#555555".into()),
+                ext: vec!["c".into(), "h".into()],
+                interpreters: vec!["tcc".into()],
+                ..Default::default()
+            }
+        );
+        assert_eq!(languages[1].ext, ["m", "h"]);
+        assert_eq!(languages[1].aliases, ["objc"]);
+        // .m is the primary extension of Objective-C only
+        assert_eq!(languages[2].ext, ["matlab"]);
+        assert_eq!(languages[3].group.as_deref(), Some("TypeScript"));
+        assert_eq!(languages[4].filenames, ["Dockerfile"]);
+
+        assert!(convert("C:\n  type: code\n").is_err());
+    }
+}
@@ -1,34 +1,1138 @@
-use clap::Parser;
-use languatage::{get_stat, LanguageStat};
+use clap::{Parser, Subcommand, ValueEnum};
+use languatage::{
+    config::ConfigSource,
+    diff_stats, get_files, get_history, get_packages, get_stat_at_rev, get_stat_with_options,
+    get_tree,
+    render::{self, HtmlOptions, MarkdownOptions, SvgOptions},
+    Config, DiffStatus, DirStat, Error, Explanation, FileStat, HistoryOptions, HistoryPoint,
+    LanguageDiff, LanguageStat, LanguageType, Metric, PackageReport, PackageStat, Report, Sampling,
+    Skipped, StatOptions,
+};
 use num_format::{Locale, ToFormattedString};
-use prettytable::{row, Table};
+use prettytable::{row, Cell, Table};
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    ffi::{OsStr, OsString},
+    io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug, Parser)]
-#[clap(author, version, about)]
+#[clap(author, version, about, args_conflicts_with_subcommands = true)]
 struct Args {
-    path: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    scan: ScanArgs,
+
+    /// Output format
+    #[clap(short, long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Draw a bar of colored squares above the markdown table
+    #[clap(long)]
+    bar: bool,
+
+    /// List every counted file with its language instead of the languages
+    #[clap(long, conflicts_with = "rev")]
+    files: bool,
+
+    /// Print a directory tree down to DEPTH levels, with the main languages of each directory
+    #[clap(
+        long,
+        value_name = "DEPTH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "2",
+        conflicts_with_all = ["files", "rev"]
+    )]
+    tree: Option<usize>,
+
+    /// Also print the languages of each package found by its manifest, like Cargo.toml,
+    /// package.json, go.mod or pyproject.toml
+    #[clap(long, conflicts_with_all = ["files", "tree", "rev"])]
+    packages: bool,
+
+    /// Order of the files listed with --files
+    #[clap(long, value_enum, default_value_t = SortKey::Path, requires = "files")]
+    sort: SortKey,
+
+    /// Only list files of these languages, by name or alias
+    #[clap(long, value_name = "LANG", value_delimiter = ',', requires = "files")]
+    lang: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortKey {
+    /// By path
+    Path,
+    /// By language, then by path
+    Lang,
+    /// Largest first
+    Size,
+    /// Most lines first
+    Lines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExplainFormat {
+    /// The language, the rule and the config layer it came from
+    Text,
+    /// JSON document of the explanation
+    Json,
+    /// YAML document with the same schema as JSON
+    Yaml,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum HistoryFormat {
+    /// One row per language of each commit
+    Csv,
+    /// Like csv, separated by tabs
+    Tsv,
+    /// JSON document, see "Output schema" in the README
+    Json,
+    /// YAML document with the same schema as JSON
+    Yaml,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DiffFormat {
+    /// Human-readable table
+    Table,
+    /// JSON document, see "Output schema" in the README
+    Json,
+    /// YAML document with the same schema as JSON
+    Yaml,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+    /// Summary and GitHub Flavored Markdown table, e.g. for a pull request comment
+    Markdown,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print a shields-style SVG badge of the top language
+    Badge {
+        #[clap(flatten)]
+        scan: ScanArgs,
+    },
+    /// Explain which rule decides whether and as which language a path is counted
+    Explain {
+        /// File or directory to explain
+        file: PathBuf,
+        #[clap(flatten)]
+        scan: ScanArgs,
+        /// Output format
+        #[clap(short, long, value_enum, default_value_t = ExplainFormat::Text)]
+        format: ExplainFormat,
+    },
+    /// Print the languages of the commits in the first-parent history of --rev (default HEAD),
+    /// oldest first
+    History {
+        #[clap(flatten)]
+        scan: ScanArgs,
+        /// Only count every Nth commit, starting with the newest
+        #[clap(long, value_name = "N", conflicts_with = "weekly")]
+        every: Option<NonZeroUsize>,
+        /// Only count one commit per week, starting with the newest
+        #[clap(long)]
+        weekly: bool,
+        /// Only count this many of the newest commits that are picked
+        #[clap(long, value_name = "N")]
+        limit: Option<usize>,
+        /// Output format
+        #[clap(short, long, value_enum, default_value_t = HistoryFormat::Csv)]
+        format: HistoryFormat,
+    },
+    /// Compare the languages of two directories or two git revisions of PATH
+    Diff {
+        /// Old directory or git revision
+        old: OsString,
+        /// New directory or git revision
+        new: OsString,
+        #[clap(flatten)]
+        scan: ScanArgs,
+        /// Output format
+        #[clap(short, long, value_enum, default_value_t = DiffFormat::Table)]
+        format: DiffFormat,
+    },
+    /// Convert GitHub linguist's languages.yml to a languatage config and print it
+    ImportLinguist {
+        /// Path of languages.yml
+        file: PathBuf,
+    },
 }
 
-fn main() {
+/// Arguments that control what is scanned and how.
+#[derive(Debug, clap::Args)]
+struct ScanArgs {
+    /// Directory to scan
+    ///
+    /// Write a directory named like a subcommand as a path, like ./badge
+    #[clap(default_value = ".")]
+    path: PathBuf,
+
+    /// Use only the YAML config file at this path instead of the built-in,
+    /// user and repository-local configs
+    #[clap(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Use GitHub linguist's languages.yml at this path instead of the built-in config
+    #[clap(long, value_name = "FILE", conflicts_with = "config")]
+    linguist: Option<PathBuf>,
+
+    /// Number of threads to scan with (0 picks a number automatically)
+    #[clap(short = 'j', long, value_name = "N", default_value_t = 0)]
+    threads: usize,
+
+    /// Also count files ignored by .gitignore, .ignore and git excludes
+    #[clap(long)]
+    no_gitignore: bool,
+
+    /// Detect the language of unmatched files from Vim and Emacs modelines
+    #[clap(long)]
+    modelines: bool,
+
+    /// Fail instead of warning when a path can't be read
+    #[clap(long)]
+    strict: bool,
+
+    /// Quantity to compute percentages from
+    #[clap(short, long, value_enum, default_value_t = Metric::Bytes)]
+    metric: Metric,
+
+    /// Count code, comment and blank lines
+    #[clap(short, long)]
+    lines: bool,
+
+    /// Leave out languages of these types, e.g. `data` to count prose as well
+    #[clap(
+        long,
+        value_enum,
+        value_name = "TYPE",
+        value_delimiter = ',',
+        default_values_t = [LanguageType::Data, LanguageType::Prose]
+    )]
+    exclude_type: Vec<LanguageType>,
+
+    /// Count languages of all types, including data and prose
+    #[clap(long, conflicts_with = "exclude_type")]
+    all_types: bool,
+
+    /// Count the files of this git revision, e.g. a tag, instead of the working tree.
+    /// They are read from the repository without a checkout
+    #[clap(long, value_name = "COMMIT-ISH")]
+    rev: Option<String>,
+}
+
+/// The result of a scan.
+struct Scan {
+    config: Config,
+    options: StatOptions,
+    report: Report,
+}
+
+impl ScanArgs {
+    /// Loads the config and applies the flags to it.
+    fn load(&self) -> anyhow::Result<(Config, StatOptions)> {
+        let mut config = match (&self.config, &self.linguist) {
+            (Some(config), _) => Config::from_path(config)?,
+            (None, Some(linguist)) => {
+                Config::from_linguist_path(linguist)?.with_layers(&self.path)?
+            }
+            (None, None) => Config::discover(&self.path)?,
+        };
+
+        if self.no_gitignore {
+            config.common.gitignore = false;
+        }
+        if self.modelines {
+            config.common.modelines = true;
+        }
+
+        for (ext, langs) in config.overlapping_extensions() {
+            eprintln!(
+                "warning: .{ext} files are counted as {}, not {}",
+                langs[0],
+                langs[1..].join(", ")
+            );
+        }
+
+        let options = StatOptions {
+            threads: self.threads,
+            metric: self.metric,
+            lines: self.lines,
+            exclude_types: match self.all_types {
+                true => vec![],
+                false => self.exclude_type.clone(),
+            },
+        };
+
+        Ok((config, options))
+    }
+
+    /// Prints a warning for each skipped path, and fails in strict mode if there are any.
+    fn check_skipped(&self, skipped: &[Skipped]) -> anyhow::Result<()> {
+        for skipped in skipped {
+            eprintln!("warning: skipped {skipped}");
+        }
+        if self.strict && !skipped.is_empty() {
+            return Err(Error::Skipped(skipped.to_vec()).into());
+        }
+        Ok(())
+    }
+
+    /// Loads the config, scans the path and prints warnings about the config and skipped paths.
+    fn scan(&self) -> anyhow::Result<Scan> {
+        let (config, options) = self.load()?;
+        let report = match &self.rev {
+            Some(rev) => get_stat_at_rev(&self.path, rev, &config, &options)?,
+            None => get_stat_with_options(&self.path, &config, &options)?,
+        };
+        self.check_skipped(&report.skipped)?;
+
+        Ok(Scan {
+            config,
+            options,
+            report,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Human-readable table
+    Table,
+    /// JSON document, see "Output schema" in the README
+    Json,
+    /// YAML document with the same schema as JSON
+    Yaml,
+    /// One comma-separated row per language, with a header
+    Csv,
+    /// One tab-separated row per language, with a header
+    Tsv,
+    /// GitHub Flavored Markdown table
+    Markdown,
+    /// Self-contained HTML page with a language bar
+    Html,
+    /// SVG image of a language bar with a legend
+    Svg,
+}
+
+/// Version of the JSON and YAML output schema. Bumped on incompatible changes only.
+const SCHEMA_VERSION: u32 = 1;
+
+/// The JSON and YAML output document.
+#[derive(Serialize)]
+struct Output<'a> {
+    version: u32,
+    #[serde(serialize_with = "serialize_path")]
+    root: &'a Path,
+    config: &'a [ConfigSource],
+    /// Only with --rev.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rev: Option<&'a str>,
+    metric: Metric,
+    lines_counted: bool,
+    total: Total,
+    languages: &'a [LanguageStat],
+    /// Only with --packages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    packages: Option<&'a [PackageStat]>,
+    skipped: &'a [Skipped],
+}
+
+/// Serializes a path lossily, so that non-UTF-8 paths don't fail.
+fn serialize_path<S: serde::Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&path.display())
+}
+
+/// Sums of the statistics of all languages.
+#[derive(Default, Serialize)]
+struct Total {
+    files: u64,
+    size: u64,
+    lines: u64,
+    code: u64,
+    comments: u64,
+    blanks: u64,
+}
+
+impl Total {
+    fn new(stats: &[LanguageStat]) -> Self {
+        stats.iter().fold(Self::default(), |total, stat| Self {
+            files: total.files + stat.files,
+            size: total.size + stat.size,
+            lines: total.lines + stat.lines,
+            code: total.code + stat.code,
+            comments: total.comments + stat.comments,
+            blanks: total.blanks + stat.blanks,
+        })
+    }
+}
+
+fn print_explanation(explanation: &Explanation) {
+    let lang = explanation.lang.as_deref().unwrap_or("not counted");
+    println!("{}: {lang}", explanation.path.display());
+    println!("rule: {}", explanation.reason);
+    if let Some(source) = &explanation.source {
+        println!("from: {source}");
+    }
+    // the last rejection is already the rule if no language counts the file
+    let rejected = explanation
+        .rejected
+        .iter()
+        .filter(|v| v.reason != explanation.reason);
+    for rejected in rejected {
+        match &rejected.source {
+            Some(source) => println!("skipped: {} (from {source})", rejected.reason),
+            None => println!("skipped: {}", rejected.reason),
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
     let arg = Args::parse();
-    let path = arg.path;
 
-    let stat = get_stat(path).unwrap();
+    match arg.command {
+        Some(Command::Badge { scan }) => {
+            let Scan { report, .. } = scan.scan()?;
+            print!("{}", render::svg_badge(&report.stats));
+            return Ok(());
+        }
+        Some(Command::Explain { file, scan, format }) => {
+            if scan.rev.is_some() {
+                anyhow::bail!("explain doesn't support --rev");
+            }
+            let (config, options) = scan.load()?;
+            let explanation = languatage::explain(&scan.path, file, &config, &options)?;
+            match format {
+                ExplainFormat::Text => print_explanation(&explanation),
+                ExplainFormat::Json => {
+                    serde_json::to_writer_pretty(io::stdout().lock(), &explanation)?;
+                    println!();
+                }
+                ExplainFormat::Yaml => serde_yaml::to_writer(io::stdout().lock(), &explanation)?,
+            }
+            return Ok(());
+        }
+        Some(Command::History {
+            scan,
+            every,
+            weekly,
+            limit,
+            format,
+        }) => {
+            let sampling = match (every, weekly) {
+                (Some(n), _) => Sampling::Every(n),
+                (None, true) => Sampling::Weekly,
+                (None, false) => Sampling::All,
+            };
+            return print_history(&scan, &HistoryOptions { sampling, limit }, format);
+        }
+        Some(Command::Diff {
+            old,
+            new,
+            scan,
+            format,
+        }) => return print_diff(&old, &new, &scan, format),
+        Some(Command::ImportLinguist { file }) => {
+            let config = Config::from_linguist_path(file)?;
+            serde_yaml::to_writer(io::stdout().lock(), &config)?;
+            return Ok(());
+        }
+        None => {}
+    }
+
+    if arg.files {
+        return list_files(&arg);
+    }
+    if let Some(depth) = arg.tree {
+        return print_tree(&arg, depth);
+    }
+    if arg.packages {
+        return print_packages(&arg);
+    }
+
+    let path = &arg.scan.path;
+    let Scan {
+        config,
+        options,
+        report,
+    } = arg.scan.scan()?;
+
+    let output = Output {
+        version: SCHEMA_VERSION,
+        root: path,
+        config: &config.sources,
+        rev: arg.scan.rev.as_deref(),
+        metric: options.metric,
+        lines_counted: options.counts_lines(),
+        total: Total::new(&report.stats),
+        languages: &report.stats,
+        packages: None,
+        skipped: &report.skipped,
+    };
+
+    match arg.format {
+        Format::Table => print_table(&report.stats, options.counts_lines()),
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), &output)?;
+            println!();
+        }
+        Format::Yaml => serde_yaml::to_writer(io::stdout().lock(), &output)?,
+        Format::Csv => write_delimited(&report, options.counts_lines(), b',')?,
+        Format::Tsv => write_delimited(&report, options.counts_lines(), b'\t')?,
+        Format::Markdown => {
+            let options = MarkdownOptions {
+                lines: options.counts_lines(),
+                bar: arg.bar,
+            };
+            print!("{}", render::markdown(&report.stats, &options));
+        }
+        Format::Html => {
+            let options = HtmlOptions {
+                title: format!("Languages of {}", path.display()),
+                lines: options.counts_lines(),
+            };
+            print!("{}", render::html(&report.stats, &options));
+        }
+        Format::Svg => print!("{}", render::svg_bar(&report.stats, &SvgOptions::default())),
+    }
+
+    Ok(())
+}
+
+/// The JSON and YAML output document of --files.
+#[derive(Serialize)]
+struct FilesOutput<'a> {
+    version: u32,
+    #[serde(serialize_with = "serialize_path")]
+    root: &'a Path,
+    config: &'a [ConfigSource],
+    lines_counted: bool,
+    files: &'a [FileStat],
+    skipped: &'a [Skipped],
+}
+
+fn list_files(arg: &Args) -> anyhow::Result<()> {
+    let scan = &arg.scan;
+    let (config, options) = scan.load()?;
+    let mut report = get_files(&scan.path, &config, &options)?;
+    scan.check_skipped(&report.skipped)?;
+
+    if !arg.lang.is_empty() {
+        let langs = filter_langs(&config, &arg.lang)?;
+        report
+            .files
+            .retain(|file| langs.contains(&file.lang.as_str()));
+    }
+
+    let files = &mut report.files;
+    match arg.sort {
+        SortKey::Path => {}
+        SortKey::Lang => files.sort_by(|a, b| a.lang.cmp(&b.lang)),
+        SortKey::Size => files.sort_by_key(|file| Reverse(file.size)),
+        SortKey::Lines => files.sort_by_key(|file| Reverse(file.lines.unwrap_or_default().lines)),
+    }
+
+    let output = FilesOutput {
+        version: SCHEMA_VERSION,
+        root: &scan.path,
+        config: &config.sources,
+        lines_counted: options.counts_lines(),
+        files: &report.files,
+        skipped: &report.skipped,
+    };
+
+    match arg.format {
+        Format::Table => print_files_table(&report.files, options.counts_lines()),
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), &output)?;
+            println!();
+        }
+        Format::Yaml => serde_yaml::to_writer(io::stdout().lock(), &output)?,
+        Format::Csv => write_files_delimited(&report.files, b',')?,
+        Format::Tsv => write_files_delimited(&report.files, b'\t')?,
+        Format::Markdown | Format::Html | Format::Svg => {
+            anyhow::bail!("--files only supports the table, json, yaml, csv and tsv formats")
+        }
+    }
+
+    Ok(())
+}
+
+/// The JSON and YAML output document of --tree.
+#[derive(Serialize)]
+struct TreeOutput<'a> {
+    version: u32,
+    #[serde(serialize_with = "serialize_path")]
+    root: &'a Path,
+    config: &'a [ConfigSource],
+    metric: Metric,
+    lines_counted: bool,
+    tree: &'a DirStat,
+    skipped: &'a [Skipped],
+}
+
+fn print_tree(arg: &Args, depth: usize) -> anyhow::Result<()> {
+    let scan = &arg.scan;
+    let (config, options) = scan.load()?;
+    let report = get_tree(&scan.path, &config, &options, depth)?;
+    scan.check_skipped(&report.skipped)?;
+
+    let output = TreeOutput {
+        version: SCHEMA_VERSION,
+        root: &scan.path,
+        config: &config.sources,
+        metric: options.metric,
+        lines_counted: options.counts_lines(),
+        tree: &report.root,
+        skipped: &report.skipped,
+    };
+
+    match arg.format {
+        Format::Table => {
+            println!("{}  {}", scan.path.display(), main_languages(&report.root));
+            print_subtree(&report.root, "");
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), &output)?;
+            println!();
+        }
+        Format::Yaml => serde_yaml::to_writer(io::stdout().lock(), &output)?,
+        _ => anyhow::bail!("--tree only supports the table, json and yaml formats"),
+    }
+
+    Ok(())
+}
+
+/// Prints the children of `dir` as the branches of a tree, each line starting with `prefix`.
+fn print_subtree(dir: &DirStat, prefix: &str) {
+    for (i, child) in dir.children.iter().enumerate() {
+        let last = i + 1 == dir.children.len();
+        let name = child.path.file_name().unwrap_or_default().to_string_lossy();
+        let branch = if last { "└── " } else { "├── " };
+        println!("{prefix}{branch}{name}  {}", main_languages(child));
+
+        let indent = if last { "    " } else { "│   " };
+        print_subtree(child, &format!("{prefix}{indent}"));
+    }
+}
+
+/// Returns the largest languages of `dir` with their percentages, like `Rust 80.0%, Go 20.0%`.
+fn main_languages(dir: &DirStat) -> String {
+    const SHOWN: usize = 3;
+
+    let mut langs: Vec<_> = dir
+        .stats
+        .iter()
+        .take(SHOWN)
+        .map(|stat| format!("{} {:.1}%", stat.lang, stat.percentage))
+        .collect();
+    if dir.stats.len() > SHOWN {
+        langs.push(format!("+{} more", dir.stats.len() - SHOWN));
+    }
+
+    langs.join(", ")
+}
+
+fn print_packages(arg: &Args) -> anyhow::Result<()> {
+    let scan = &arg.scan;
+    let (config, options) = scan.load()?;
+    let report = get_packages(&scan.path, &config, &options)?;
+    scan.check_skipped(&report.skipped)?;
+
+    let output = Output {
+        version: SCHEMA_VERSION,
+        root: &scan.path,
+        config: &config.sources,
+        rev: None,
+        metric: options.metric,
+        lines_counted: options.counts_lines(),
+        total: Total::new(&report.stats),
+        languages: &report.stats,
+        packages: Some(&report.packages),
+        skipped: &report.skipped,
+    };
+
+    match arg.format {
+        Format::Table => {
+            print_table(&report.stats, options.counts_lines());
+            for package in &report.packages {
+                println!();
+                println!("{}", package_title(package));
+                print_table(&package.stats, options.counts_lines());
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), &output)?;
+            println!();
+        }
+        Format::Yaml => serde_yaml::to_writer(io::stdout().lock(), &output)?,
+        Format::Csv => write_packages_delimited(&report, options.counts_lines(), b',')?,
+        Format::Tsv => write_packages_delimited(&report, options.counts_lines(), b'\t')?,
+        Format::Markdown | Format::Html | Format::Svg => {
+            anyhow::bail!("--packages only supports the table, json, yaml, csv and tsv formats")
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the path of a package for printing, `.` for the scanned directory itself.
+fn package_path(package: &PackageStat) -> String {
+    match package.path.as_os_str().is_empty() {
+        true => ".".into(),
+        false => package.path.display().to_string(),
+    }
+}
+
+/// Returns a heading for a package, like `crates/app (app, cargo)`.
+fn package_title(package: &PackageStat) -> String {
+    let path = package_path(package);
+    let kinds = package.kinds.iter().map(|kind| kind.to_string());
+
+    let details: Vec<_> = package.name.iter().cloned().chain(kinds).collect();
+    format!("{path} ({})", details.join(", "))
+}
+
+/// Writes one row per language of each package, with the package path, name and kinds,
+/// after the rows of all packages together, whose package, name and kinds are empty.
+fn write_packages_delimited(
+    report: &PackageReport,
+    lines: bool,
+    delimiter: u8,
+) -> anyhow::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_writer(io::stdout().lock());
+    let columns = ["package", "name", "kinds"]
+        .iter()
+        .chain(&LANGUAGE_STAT_COLUMNS);
+    writer.write_record(columns)?;
+    for stat in &report.stats {
+        writer.serialize(("", "", "", LanguageStatRow::new(stat, lines)))?;
+    }
+    for package in &report.packages {
+        let kinds: Vec<_> = package.kinds.iter().map(|kind| kind.to_string()).collect();
+        for stat in &package.stats {
+            writer.serialize((
+                package_path(package),
+                package.name.as_deref().unwrap_or_default(),
+                kinds.join(" "),
+                LanguageStatRow::new(stat, lines),
+            ))?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The JSON and YAML output document of the history command.
+#[derive(Serialize)]
+struct HistoryOutput<'a> {
+    version: u32,
+    #[serde(serialize_with = "serialize_path")]
+    root: &'a Path,
+    config: &'a [ConfigSource],
+    rev: &'a str,
+    metric: Metric,
+    lines_counted: bool,
+    commits: &'a [HistoryPoint],
+}
+
+fn print_history(
+    scan: &ScanArgs,
+    history: &HistoryOptions,
+    format: HistoryFormat,
+) -> anyhow::Result<()> {
+    let (config, options) = scan.load()?;
+    let rev = scan.rev.as_deref().unwrap_or("HEAD");
+    let points = get_history(&scan.path, rev, &config, &options, history)?;
+    for point in &points {
+        scan.check_skipped(&point.skipped)?;
+    }
+
+    let output = HistoryOutput {
+        version: SCHEMA_VERSION,
+        root: &scan.path,
+        config: &config.sources,
+        rev,
+        metric: options.metric,
+        lines_counted: options.counts_lines(),
+        commits: &points,
+    };
+
+    match format {
+        HistoryFormat::Csv => write_history_delimited(&points, options.counts_lines(), b',')?,
+        HistoryFormat::Tsv => write_history_delimited(&points, options.counts_lines(), b'\t')?,
+        HistoryFormat::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), &output)?;
+            println!();
+        }
+        HistoryFormat::Yaml => serde_yaml::to_writer(io::stdout().lock(), &output)?,
+    }
+
+    Ok(())
+}
+
+/// Writes one row per language of each commit, with the commit hash, time and date.
+fn write_history_delimited(
+    points: &[HistoryPoint],
+    lines: bool,
+    delimiter: u8,
+) -> anyhow::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_writer(io::stdout().lock());
+    let columns = ["commit", "time", "date"]
+        .iter()
+        .chain(&LANGUAGE_STAT_COLUMNS);
+    writer.write_record(columns)?;
+    for point in points {
+        for stat in &point.stats {
+            writer.serialize((
+                &point.commit,
+                point.time,
+                &point.date,
+                LanguageStatRow::new(stat, lines),
+            ))?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The JSON and YAML output document of the diff command.
+#[derive(Serialize)]
+struct DiffOutput<'a> {
+    version: u32,
+    #[serde(serialize_with = "serialize_path")]
+    root: &'a Path,
+    config: &'a [ConfigSource],
+    #[serde(serialize_with = "serialize_path")]
+    old: &'a Path,
+    #[serde(serialize_with = "serialize_path")]
+    new: &'a Path,
+    metric: Metric,
+    lines_counted: bool,
+    languages: &'a [LanguageDiff],
+}
+
+fn print_diff(old: &OsStr, new: &OsStr, scan: &ScanArgs, format: DiffFormat) -> anyhow::Result<()> {
+    if scan.rev.is_some() {
+        anyhow::bail!("diff takes revisions instead of --rev");
+    }
+    let (config, options) = scan.load()?;
+
+    // a directory, or else a revision of the repository PATH is in
+    let stats = |side: &OsStr| -> anyhow::Result<Vec<LanguageStat>> {
+        let dir = Path::new(side);
+        let rev = side.to_str().filter(|rev| is_revision(&scan.path, rev));
+        let report = match (dir.is_dir(), rev) {
+            (true, Some(rev)) => anyhow::bail!(
+                "{rev} is both a directory and a git revision; \
+                 write ./{rev} for the directory or {rev}^{{commit}} for the revision"
+            ),
+            (true, None) => get_stat_with_options(dir, &config, &options)?,
+            (false, _) => match side.to_str() {
+                Some(rev) => get_stat_at_rev(&scan.path, rev, &config, &options)?,
+                None => anyhow::bail!(
+                    "{} is neither a directory nor a git revision",
+                    dir.display()
+                ),
+            },
+        };
+        scan.check_skipped(&report.skipped)?;
+        Ok(report.stats)
+    };
+    let diffs = diff_stats(&stats(old)?, &stats(new)?);
+
+    let output = DiffOutput {
+        version: SCHEMA_VERSION,
+        root: &scan.path,
+        config: &config.sources,
+        old: Path::new(old),
+        new: Path::new(new),
+        metric: options.metric,
+        lines_counted: options.counts_lines(),
+        languages: &diffs,
+    };
+
+    match format {
+        DiffFormat::Table => print_diff_table(&diffs, options.counts_lines()),
+        DiffFormat::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), &output)?;
+            println!();
+        }
+        DiffFormat::Yaml => serde_yaml::to_writer(io::stdout().lock(), &output)?,
+        DiffFormat::Csv => write_diff_delimited(&diffs, b',')?,
+        DiffFormat::Tsv => write_diff_delimited(&diffs, b'\t')?,
+        DiffFormat::Markdown => print!("{}", render::markdown_diff(&diffs, options.counts_lines())),
+    }
+
+    Ok(())
+}
+
+/// Returns whether `rev` names a revision of the git repository that `path` is in.
+fn is_revision(path: &Path, rev: &str) -> bool {
+    git2::Repository::discover(path).is_ok_and(|repo| repo.revparse_single(rev).is_ok())
+}
+
+fn print_diff_table(diffs: &[LanguageDiff], lines: bool) {
+    for (status, title) in [
+        (DiffStatus::Added, "New languages"),
+        (DiffStatus::Removed, "Removed languages"),
+    ] {
+        let langs: Vec<_> = diffs
+            .iter()
+            .filter(|v| v.status == status)
+            .map(|v| v.lang.as_str())
+            .collect();
+        if !langs.is_empty() {
+            println!("{title}: {}", langs.join(", "));
+        }
+    }
+
+    let mut header = row![b->"Language", b->"Change", b->"Percentage", b->"Files", b->"Size"];
+    if lines {
+        for title in ["Lines", "Code"] {
+            header.add_cell(Cell::new(title).style_spec("b"));
+        }
+    }
+
+    let mut table = Table::init(vec![header]);
+    for diff in diffs.iter().filter(|v| v.status != DiffStatus::Unchanged) {
+        let mut row = row![
+            diff.lang,
+            diff.status,
+            r->format!("{:+.2} pp", diff.percentage),
+            r->render::signed_digits(diff.files),
+            r->render::signed_digits(diff.size)
+        ];
+        if lines {
+            for count in [diff.lines, diff.code] {
+                row.add_cell(Cell::new(&render::signed_digits(count)).style_spec("r"));
+            }
+        }
+        table.add_row(row);
+    }
+
+    table.printstd();
+}
+
+/// Writes one row per language with its status and changes.
+fn write_diff_delimited(diffs: &[LanguageDiff], delimiter: u8) -> anyhow::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(io::stdout().lock());
+    writer.write_record([
+        "lang",
+        "status",
+        "size",
+        "files",
+        "lines",
+        "code",
+        "comments",
+        "blanks",
+        "percentage",
+    ])?;
+    for diff in diffs {
+        writer.serialize((
+            &diff.lang,
+            diff.status,
+            diff.size,
+            diff.files,
+            diff.lines,
+            diff.code,
+            diff.comments,
+            diff.blanks,
+            diff.percentage,
+        ))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Returns the names that files of the languages in `filter` are counted as.
+/// Languages are matched case-insensitively by name, group or alias.
+fn filter_langs<'a>(config: &'a Config, filter: &[String]) -> anyhow::Result<Vec<&'a str>> {
+    let mut langs = vec![];
+    for name in filter {
+        let matched: Vec<_> = config
+            .language
+            .iter()
+            .filter(|language| {
+                [&language.lang]
+                    .into_iter()
+                    .chain(&language.group)
+                    .chain(&language.aliases)
+                    .any(|v| v.eq_ignore_ascii_case(name))
+            })
+            .map(|language| language.counted_as())
+            .collect();
+        if matched.is_empty() {
+            anyhow::bail!("unknown language: {name}");
+        }
+        langs.extend(matched);
+    }
+    Ok(langs)
+}
+
+fn print_files_table(files: &[FileStat], lines: bool) {
+    let mut header = row![b->"Path", b->"Language", b->"Size"];
+    if lines {
+        for title in ["Lines", "Code", "Comments", "Blanks"] {
+            header.add_cell(Cell::new(title).style_spec("b"));
+        }
+    }
+
+    let mut table = Table::init(vec![header]);
+
+    for file in files {
+        let mut row = row![
+            file.path.display(),
+            file.lang,
+            r->file.size.to_formatted_string(&Locale::en)
+        ];
+        if let Some(count) = file.lines {
+            for count in [count.lines, count.code, count.comments, count.blanks] {
+                row.add_cell(Cell::new(&count.to_formatted_string(&Locale::en)).style_spec("r"));
+            }
+        }
+        table.add_row(row);
+    }
+
+    table.printstd();
+}
+
+fn write_files_delimited(files: &[FileStat], delimiter: u8) -> anyhow::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(io::stdout().lock());
+    writer.write_record([
+        "path", "lang", "size", "lines", "code", "comments", "blanks",
+    ])?;
+    for file in files {
+        let mut record = vec![
+            file.path.display().to_string(),
+            file.lang.clone(),
+            file.size.to_string(),
+        ];
+        // line counts are left empty unless they were counted
+        match file.lines {
+            Some(v) => {
+                record.extend([v.lines, v.code, v.comments, v.blanks].map(|n| n.to_string()))
+            }
+            None => record.resize(7, String::new()),
+        }
+        writer.write_record(record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Columns of a [`LanguageStat`] in CSV and TSV output, in the order of its fields.
+/// The header is written by hand, so that it is there even if no language was found.
+const LANGUAGE_STAT_COLUMNS: [&str; 10] = [
+    "lang",
+    "size",
+    "percentage",
+    "files",
+    "lines",
+    "code",
+    "comments",
+    "blanks",
+    "color",
+    "type",
+];
+
+/// A [`LanguageStat`] as a CSV or TSV row, with the line counts left empty
+/// unless they were counted.
+#[derive(Serialize)]
+struct LanguageStatRow<'a> {
+    lang: &'a str,
+    size: u64,
+    percentage: f64,
+    files: u64,
+    lines: Option<u64>,
+    code: Option<u64>,
+    comments: Option<u64>,
+    blanks: Option<u64>,
+    color: Option<&'a str>,
+    kind: LanguageType,
+}
+
+impl<'a> LanguageStatRow<'a> {
+    fn new(stat: &'a LanguageStat, lines: bool) -> Self {
+        let counted = |count| Some(count).filter(|_| lines);
+        Self {
+            lang: &stat.lang,
+            size: stat.size,
+            percentage: stat.percentage,
+            files: stat.files,
+            lines: counted(stat.lines),
+            code: counted(stat.code),
+            comments: counted(stat.comments),
+            blanks: counted(stat.blanks),
+            color: stat.color.as_deref(),
+            kind: stat.kind,
+        }
+    }
+}
+
+fn write_delimited(report: &Report, lines: bool, delimiter: u8) -> anyhow::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_writer(io::stdout().lock());
+    writer.write_record(LANGUAGE_STAT_COLUMNS)?;
+    for stat in &report.stats {
+        writer.serialize(LanguageStatRow::new(stat, lines))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn print_table(stat: &[LanguageStat], lines: bool) {
+    let mut header = row![b->"Language", b->"Percentage", b->"Files", b->"Size"];
+    if lines {
+        for title in ["Lines", "Code", "Comments", "Blanks"] {
+            header.add_cell(Cell::new(title).style_spec("b"));
+        }
+    }
 
-    let mut table = Table::init(vec![row![b->"Language", b->"Percentage", b->"Size"]]);
+    let mut table = Table::init(vec![header]);
 
-    stat.iter().filter(|stat| stat.size != 0).for_each(|stat| {
+    stat.iter().for_each(|stat| {
         let LanguageStat {
             lang,
             percentage,
             size,
+            files,
             ..
         } = stat;
-        table.add_row(row![
+        let mut row = row![
             lang,
             r->format!("{: >5}%", (percentage * 100.0).round() / 100.0),
+            r->files.to_formatted_string(&Locale::en),
             r->size.to_formatted_string(&Locale::en)
-        ]);
+        ];
+        if lines {
+            for count in [stat.lines, stat.code, stat.comments, stat.blanks] {
+                row.add_cell(Cell::new(&count.to_formatted_string(&Locale::en)).style_spec("r"));
+            }
+        }
+        table.add_row(row);
     });
 
     table.printstd();
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::{ffi::OsStr, fmt, fs, path::Path};
+
+/// A kind of package manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    /// `Cargo.toml`, including virtual workspace manifests.
+    Cargo,
+    /// `package.json`, including workspace roots.
+    Npm,
+    /// `go.mod`.
+    Go,
+    /// `pyproject.toml` or `setup.py`.
+    Python,
+    /// `pom.xml`.
+    Maven,
+    /// `build.gradle` or `build.gradle.kts`.
+    Gradle,
+    /// `composer.json`.
+    Composer,
+    /// `pubspec.yaml`.
+    Dart,
+}
+
+impl fmt::Display for PackageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::Go => "go",
+            Self::Python => "python",
+            Self::Maven => "maven",
+            Self::Gradle => "gradle",
+            Self::Composer => "composer",
+            Self::Dart => "dart",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// File names of the manifests that make their directory a package.
+const MANIFESTS: &[(&str, PackageKind)] = &[
+    ("Cargo.toml", PackageKind::Cargo),
+    ("package.json", PackageKind::Npm),
+    ("go.mod", PackageKind::Go),
+    ("pyproject.toml", PackageKind::Python),
+    ("setup.py", PackageKind::Python),
+    ("pom.xml", PackageKind::Maven),
+    ("build.gradle", PackageKind::Gradle),
+    ("build.gradle.kts", PackageKind::Gradle),
+    ("composer.json", PackageKind::Composer),
+    ("pubspec.yaml", PackageKind::Dart),
+];
+
+/// A manifest read by [`read_manifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Manifest {
+    pub(crate) kind: PackageKind,
+    /// Name of the package, if the manifest has one that can be read.
+    pub(crate) name: Option<String>,
+}
+
+/// Returns the manifest at `path`, or `None` if the file isn't a manifest.
+pub(crate) fn read_manifest(path: &Path) -> Option<Manifest> {
+    let kind = MANIFESTS
+        .iter()
+        .find(|(name, _)| path.file_name() == Some(OsStr::new(name)))
+        .map(|&(_, kind)| kind)?;
+
+    // a manifest that can't be read or parsed still marks a package, just without a name
+    Some(Manifest {
+        kind,
+        name: fs::read_to_string(path)
+            .ok()
+            .and_then(|content| package_name(kind, &content)),
+    })
+}
+
+/// Reads the package name from the content of a manifest of `kind`.
+fn package_name(kind: PackageKind, content: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Named {
+        name: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct CargoManifest {
+        package: Option<Named>,
+    }
+
+    #[derive(Deserialize)]
+    struct Tool {
+        poetry: Option<Named>,
+    }
+
+    #[derive(Deserialize)]
+    struct PyProject {
+        project: Option<Named>,
+        tool: Option<Tool>,
+    }
+
+    match kind {
+        PackageKind::Cargo => toml::from_str::<CargoManifest>(content).ok()?.package?.name,
+        PackageKind::Python => {
+            let pyproject = toml::from_str::<PyProject>(content).ok()?;
+            pyproject
+                .project
+                .and_then(|v| v.name)
+                .or_else(|| pyproject.tool?.poetry?.name)
+        }
+        PackageKind::Npm | PackageKind::Composer => {
+            serde_json::from_str::<Named>(content).ok()?.name
+        }
+        PackageKind::Dart => serde_yaml::from_str::<Named>(content).ok()?.name,
+        PackageKind::Go => content.lines().find_map(|line| {
+            let module = line.trim().strip_prefix("module")?;
+            // `module` must be followed by whitespace, and the path may be quoted
+            module
+                .starts_with(char::is_whitespace)
+                .then(|| module.trim().trim_matches('"').to_string())
+        }),
+        PackageKind::Maven | PackageKind::Gradle => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_name_test() {
+        let name = |kind, content| package_name(kind, content);
+
+        assert_eq!(
+            name(PackageKind::Cargo, "[package]\nname = \"app\"\n").as_deref(),
+            Some("app")
+        );
+        assert_eq!(
+            name(PackageKind::Cargo, "[workspace]\nmembers = [\"app\"]\n"),
+            None
+        );
+        assert_eq!(
+            name(
+                PackageKind::Npm,
+                r#"{ "name": "@org/web", "private": true }"#
+            )
+            .as_deref(),
+            Some("@org/web")
+        );
+        assert_eq!(
+            name(
+                PackageKind::Go,
+                "// comment\nmodule example.com/api\n\ngo 1.21\n"
+            )
+            .as_deref(),
+            Some("example.com/api")
+        );
+        assert_eq!(
+            name(PackageKind::Python, "[project]\nname = \"tool\"\n").as_deref(),
+            Some("tool")
+        );
+        assert_eq!(
+            name(PackageKind::Python, "[tool.poetry]\nname = \"legacy\"\n").as_deref(),
+            Some("legacy")
+        );
+        assert_eq!(
+            name(PackageKind::Dart, "name: app\n").as_deref(),
+            Some("app")
+        );
+        assert_eq!(name(PackageKind::Npm, "{"), None);
+    }
+}
@@ -0,0 +1,99 @@
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use std::path::Path;
+
+/// A list of gitignore-style glob patterns relative to a root directory.
+///
+/// A pattern without a slash matches a file or directory with that name anywhere
+/// (`dist`, `*.generated.ts`), a pattern with a slash is relative to the root
+/// (`/vendor`, `packages/*/build`), `**` matches any number of directories
+/// and a leading `!` excludes paths matched by an earlier pattern.
+pub(crate) struct Patterns(Gitignore);
+
+impl Patterns {
+    pub(crate) fn new<P: AsRef<Path>>(root: P, patterns: &[String]) -> Result<Self, ignore::Error> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            builder.add_line(None, pattern)?;
+        }
+
+        Ok(Self(builder.build()?))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns whether `path` itself matches.
+    pub(crate) fn is_match(&self, path: &Path, is_dir: bool) -> bool {
+        self.0.matched(path, is_dir).is_ignore()
+    }
+
+    /// Returns whether `path` or any of its parent directories match.
+    /// `path` must be inside the root.
+    pub(crate) fn is_match_or_any_parents(&self, path: &Path, is_dir: bool) -> bool {
+        self.0.matched_path_or_any_parents(path, is_dir).is_ignore()
+    }
+
+    /// Returns the pattern that matches `path` itself, if any.
+    pub(crate) fn matched(&self, path: &Path, is_dir: bool) -> Option<&str> {
+        match self.0.matched(path, is_dir) {
+            Match::Ignore(glob) => Some(glob.original()),
+            _ => None,
+        }
+    }
+
+    /// Returns the pattern that matches `path` or any of its parent directories, if any.
+    /// `path` must be inside the root.
+    pub(crate) fn matched_or_any_parents(&self, path: &Path, is_dir: bool) -> Option<&str> {
+        match self.0.matched_path_or_any_parents(path, is_dir) {
+            Match::Ignore(glob) => Some(glob.original()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_test() {
+        let patterns: Vec<String> = [
+            "node_modules",
+            "**/*.generated.ts",
+            "packages/*/build",
+            "/vendor",
+            "!packages/app/build/keep",
+        ]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+        let patterns = Patterns::new("root", &patterns).unwrap();
+
+        let is_match = |path: &str| patterns.is_match_or_any_parents(Path::new(path), false);
+
+        assert!(is_match("root/a/node_modules/b.js"));
+        assert!(is_match("root/src/api.generated.ts"));
+        assert!(is_match("root/packages/app/build/main.js"));
+        assert!(is_match("root/vendor/lib.go"));
+        assert!(!is_match("root/src/api.ts"));
+        assert!(!is_match("root/packages/app/src/build.ts"));
+        assert!(!is_match("root/third_party/vendor/lib.go"));
+        assert!(!is_match("root/packages/app/build/keep"));
+
+        assert_eq!(
+            patterns.matched_or_any_parents(Path::new("root/vendor/lib.go"), false),
+            Some("/vendor")
+        );
+        assert_eq!(
+            patterns.matched(Path::new("root/vendor/lib.go"), false),
+            None
+        );
+
+        assert!(Patterns::new("root", &[]).unwrap().is_empty());
+        assert!(Patterns::new("root", &["{a,b".into()]).is_err());
+    }
+}
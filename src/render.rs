@@ -0,0 +1,652 @@
+//! Renders language statistics as Markdown, HTML and SVG.
+
+use crate::{DiffStatus, LanguageDiff, LanguageStat};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Write,
+    hash::{Hash, Hasher},
+};
+
+/// Options of [`markdown`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarkdownOptions {
+    /// Whether to add the `lines`, `code`, `comments` and `blanks` columns.
+    pub lines: bool,
+    /// Whether to draw a stacked bar of colored squares above the table.
+    pub bar: bool,
+}
+
+/// Options of [`html`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlOptions {
+    /// Title of the page.
+    pub title: String,
+    /// Whether to add the `lines`, `code`, `comments` and `blanks` columns.
+    pub lines: bool,
+}
+
+/// Options of [`svg_bar`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// Width of the image in pixels. The height follows from the number of legend rows.
+    pub width: u32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self { width: 400 }
+    }
+}
+
+/// Number of squares in the Markdown bar.
+const BAR_WIDTH: usize = 20;
+
+/// Squares of the Markdown bar, by rank of the language. Languages past the end share `OTHER_SQUARE`.
+const SQUARES: [&str; 8] = ["🟦", "🟧", "🟩", "🟥", "🟪", "🟨", "🟫", "⬛"];
+const OTHER_SQUARE: &str = "⬜";
+
+/// Colors of languages, picked by a hash of the name.
+const PALETTE: [&str; 12] = [
+    "#3572a5", "#dea584", "#00add8", "#f1e05a", "#3178c6", "#41b883", "#b07219", "#e34c26",
+    "#563d7c", "#701516", "#89e051", "#c22d40",
+];
+
+/// Returns a GitHub Flavored Markdown table of `stats`.
+/// ```rust
+/// use languatage::{get_stat, render::{markdown, MarkdownOptions}};
+///
+/// let report = get_stat(".").unwrap();
+/// let table: String = markdown(&report.stats, &MarkdownOptions::default());
+/// ```
+pub fn markdown(stats: &[LanguageStat], options: &MarkdownOptions) -> String {
+    let mut out = String::new();
+
+    if options.bar {
+        let cells = bar_cells(stats, BAR_WIDTH);
+        for (i, count) in cells.into_iter().enumerate() {
+            out.push_str(&square(i).repeat(count));
+        }
+        out.push_str("\n\n");
+    }
+
+    out.push_str("| Language | Percentage | Files | Size |");
+    if options.lines {
+        out.push_str(" Lines | Code | Comments | Blanks |");
+    }
+    out.push_str("\n| :-- | --: | --: | --: |");
+    if options.lines {
+        out.push_str(" --: | --: | --: | --: |");
+    }
+    out.push('\n');
+
+    for (i, stat) in stats.iter().enumerate() {
+        let lang = markdown_escape(&stat.lang);
+        let lang = match options.bar {
+            true => format!("{} {lang}", square(i)),
+            false => lang,
+        };
+        let _ = write!(
+            out,
+            "| {lang} | {:.2}% | {} | {} |",
+            stat.percentage,
+            group_digits(stat.files),
+            group_digits(stat.size)
+        );
+        if options.lines {
+            for count in [stat.lines, stat.code, stat.comments, stat.blanks] {
+                let _ = write!(out, " {} |", group_digits(count));
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Returns a GitHub Flavored Markdown summary of `diffs`, e.g. for a pull request comment:
+/// the languages that were added or removed, followed by a table of the changes of every
+/// language that changed. Line counts are only included if `lines` is set.
+pub fn markdown_diff(diffs: &[LanguageDiff], lines: bool) -> String {
+    let mut out = String::new();
+
+    for (status, title) in [
+        (DiffStatus::Added, "New languages"),
+        (DiffStatus::Removed, "Removed languages"),
+    ] {
+        let langs: Vec<_> = diffs
+            .iter()
+            .filter(|v| v.status == status)
+            .map(|v| markdown_escape(&v.lang))
+            .collect();
+        if !langs.is_empty() {
+            let _ = writeln!(out, "**{title}:** {}\n", langs.join(", "));
+        }
+    }
+
+    out.push_str("| Language | Change | Percentage | Files | Size |");
+    if lines {
+        out.push_str(" Lines | Code |");
+    }
+    out.push_str("\n| :-- | :-- | --: | --: | --: |");
+    if lines {
+        out.push_str(" --: | --: |");
+    }
+    out.push('\n');
+
+    for diff in diffs.iter().filter(|v| v.status != DiffStatus::Unchanged) {
+        let _ = write!(
+            out,
+            "| {} | {} | {:+.2} pp | {} | {} |",
+            markdown_escape(&diff.lang),
+            diff.status,
+            diff.percentage,
+            signed_digits(diff.files),
+            signed_digits(diff.size)
+        );
+        if lines {
+            for count in [diff.lines, diff.code] {
+                let _ = write!(out, " {} |", signed_digits(count));
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Returns a self-contained HTML page with a colored bar of `stats` and a table of them.
+/// ```rust
+/// use languatage::{get_stat, render::{html, HtmlOptions}};
+///
+/// let report = get_stat(".").unwrap();
+/// let page: String = html(&report.stats, &HtmlOptions::default());
+/// ```
+pub fn html(stats: &[LanguageStat], options: &HtmlOptions) -> String {
+    let title = html_escape(&options.title);
+    let mut out = String::new();
+
+    let _ = write!(
+        out,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 48em; color: #1f2328; }}
+.bar {{ display: flex; height: 8px; border-radius: 6px; overflow: hidden; background: #d0d7de; }}
+.bar span {{ display: block; height: 100%; }}
+.bar span + span {{ margin-left: 2px; }}
+.legend {{ list-style: none; padding: 0; display: flex; flex-wrap: wrap; gap: 0.5em 1.5em; }}
+.dot {{ display: inline-block; width: 0.7em; height: 0.7em; border-radius: 50%; margin-right: 0.4em; }}
+table {{ border-collapse: collapse; margin-top: 1em; }}
+th, td {{ padding: 0.3em 0.8em; border-bottom: 1px solid #d0d7de; }}
+td.num {{ text-align: right; font-variant-numeric: tabular-nums; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<div class="bar">
+"#
+    );
+
+    for stat in stats {
+        let _ = writeln!(
+            out,
+            r#"<span style="width: {:.2}%; background: {}" title="{} {:.2}%"></span>"#,
+            stat.percentage,
+            stat_color(stat),
+            html_escape(&stat.lang),
+            stat.percentage
+        );
+    }
+
+    out.push_str("</div>\n<ul class=\"legend\">\n");
+    for stat in stats {
+        let _ = writeln!(
+            out,
+            r#"<li><span class="dot" style="background: {}"></span><strong>{}</strong> {:.2}%</li>"#,
+            stat_color(stat),
+            html_escape(&stat.lang),
+            stat.percentage
+        );
+    }
+
+    out.push_str(
+        "</ul>\n<table>\n<tr><th>Language</th><th>Percentage</th><th>Files</th><th>Size</th>",
+    );
+    if options.lines {
+        out.push_str("<th>Lines</th><th>Code</th><th>Comments</th><th>Blanks</th>");
+    }
+    out.push_str("</tr>\n");
+
+    for stat in stats {
+        let _ = write!(
+            out,
+            r#"<tr><td>{}</td><td class="num">{:.2}%</td><td class="num">{}</td><td class="num">{}</td>"#,
+            html_escape(&stat.lang),
+            stat.percentage,
+            group_digits(stat.files),
+            group_digits(stat.size)
+        );
+        if options.lines {
+            for count in [stat.lines, stat.code, stat.comments, stat.blanks] {
+                let _ = write!(out, r#"<td class="num">{}</td>"#, group_digits(count));
+            }
+        }
+        out.push_str("</tr>\n");
+    }
+
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+/// Returns an SVG image of a stacked bar of `stats` like GitHub's language bar,
+/// with a legend of the languages and their percentages below it.
+/// ```rust
+/// use languatage::{get_stat, render::{svg_bar, SvgOptions}};
+///
+/// let report = get_stat(".").unwrap();
+/// let image: String = svg_bar(&report.stats, &SvgOptions::default());
+/// ```
+pub fn svg_bar(stats: &[LanguageStat], options: &SvgOptions) -> String {
+    const BAR_HEIGHT: f64 = 8.0;
+    const ROW_HEIGHT: f64 = 20.0;
+    const FONT_SIZE: f64 = 12.0;
+
+    let width = options.width as f64;
+    let mut legend = String::new();
+    let (mut x, mut y) = (0.0, BAR_HEIGHT + ROW_HEIGHT);
+
+    for stat in stats {
+        let label = format!("{} {:.1}%", stat.lang, stat.percentage);
+        let item_width = 12.0 + text_width(&label, FONT_SIZE) + 16.0;
+        if x > 0.0 && x + item_width > width {
+            x = 0.0;
+            y += ROW_HEIGHT;
+        }
+        let _ = writeln!(
+            legend,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="4" fill="{}"/><text x="{:.1}" y="{y:.1}">{}</text>"#,
+            x + 4.0,
+            y - 4.0,
+            stat_color(stat),
+            x + 12.0,
+            xml_escape(&label)
+        );
+        x += item_width;
+    }
+
+    let mut segments = String::new();
+    let mut x = 0.0;
+    for stat in stats {
+        let segment = stat.percentage / 100.0 * width;
+        let _ = writeln!(
+            segments,
+            r#"<rect x="{x:.2}" width="{segment:.2}" height="{BAR_HEIGHT}" fill="{}"><title>{} {:.1}%</title></rect>"#,
+            stat_color(stat),
+            xml_escape(&stat.lang),
+            stat.percentage
+        );
+        x += segment;
+    }
+
+    let height = if stats.is_empty() {
+        BAR_HEIGHT
+    } else {
+        y + 6.0
+    };
+    let id = id_prefix(&(options.width, &segments, &legend));
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" role="img" aria-label="Languages">
+<clipPath id="{id}-bar"><rect width="{width}" height="{BAR_HEIGHT}" rx="4"/></clipPath>
+<g clip-path="url(#{id}-bar)">
+<rect width="{width}" height="{BAR_HEIGHT}" fill="#d0d7de"/>
+{segments}</g>
+<g font-family="-apple-system,Segoe UI,Helvetica,Arial,sans-serif" font-size="{FONT_SIZE}" fill="#1f2328">
+{legend}</g>
+</svg>
+"##
+    );
+    out
+}
+
+/// Returns a shields-style SVG badge of the top language in `stats`, e.g. `Rust | 92%`.
+/// ```rust
+/// use languatage::{get_stat, render::svg_badge};
+///
+/// let report = get_stat(".").unwrap();
+/// let badge: String = svg_badge(&report.stats);
+/// ```
+pub fn svg_badge(stats: &[LanguageStat]) -> String {
+    const FONT_SIZE: f64 = 11.0;
+
+    let (label, message, fill) = match stats.first() {
+        Some(stat) => (
+            stat.lang.clone(),
+            format!("{:.0}%", stat.percentage),
+            stat_color(stat),
+        ),
+        None => ("languages".to_string(), "none".to_string(), "#9f9f9f"),
+    };
+
+    let label_width = (text_width(&label, FONT_SIZE) + 10.0).round();
+    let message_width = (text_width(&message, FONT_SIZE) + 10.0).round();
+    let width = label_width + message_width;
+    let label_x = label_width / 2.0;
+    let message_x = label_width + message_width / 2.0;
+    let message_fill = text_color(fill);
+    let (label, message) = (xml_escape(&label), xml_escape(&message));
+    let id = id_prefix(&(&label, &message, fill));
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+<title>{label}: {message}</title>
+<linearGradient id="{id}-s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="{id}-r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#{id}-r)">
+<rect width="{label_width}" height="20" fill="#555"/>
+<rect x="{label_width}" width="{message_width}" height="20" fill="{fill}"/>
+<rect width="{width}" height="20" fill="url(#{id}-s)"/>
+</g>
+<g text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="{FONT_SIZE}">
+<text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+<text x="{label_x}" y="14" fill="#fff">{label}</text>
+<text x="{message_x}" y="14" fill="{message_fill}">{message}</text>
+</g>
+</svg>
+"##
+    )
+}
+
+/// Returns a prefix for the ids in an SVG image, derived from its `content`, so that images
+/// with different content don't share ids when they are inlined into the same page.
+fn id_prefix<T: Hash + ?Sized>(content: &T) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("languatage-{:08x}", hasher.finish() as u32)
+}
+
+/// Returns the color of `stat` from the config if it's a valid hex color,
+/// or else one picked by the name of the language.
+fn stat_color(stat: &LanguageStat) -> &str {
+    match &stat.color {
+        Some(color) if is_hex_color(color) => color,
+        _ => color(&stat.lang),
+    }
+}
+
+/// Returns whether `color` is a `#rgb` or `#rrggbb` hex string.
+fn is_hex_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Returns the color of `lang`, as a `#rrggbb` hex string.
+fn color(lang: &str) -> &'static str {
+    // FNV-1a, so that a language keeps its color across runs and Rust versions
+    let hash = lang.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    PALETTE[(hash % PALETTE.len() as u64) as usize]
+}
+
+/// Returns black or white, whichever is more readable on `background`, a `#rgb` or
+/// `#rrggbb` hex string.
+fn text_color(background: &str) -> &'static str {
+    let hex = background.trim_start_matches('#');
+    let channel = |i: usize| {
+        let value = match hex.len() {
+            3 => hex.get(i..i + 1).map(|v| v.repeat(2)),
+            _ => hex.get(i * 2..i * 2 + 2).map(str::to_string),
+        };
+        value
+            .and_then(|v| u8::from_str_radix(&v, 16).ok())
+            .unwrap_or(0) as f64
+    };
+    let luminance = 0.299 * channel(0) + 0.587 * channel(1) + 0.114 * channel(2);
+    if luminance > 160.0 {
+        "#1f2328"
+    } else {
+        "#fff"
+    }
+}
+
+/// Estimates the width of `text` in pixels, as SVG has no way to measure it.
+fn text_width(text: &str, font_size: f64) -> f64 {
+    let em: f64 = text
+        .chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | '\'' | '|' | '!' | ':' => 0.3,
+            'f' | 't' | 'r' | ' ' | '(' | ')' | '-' => 0.4,
+            'm' | 'w' | 'M' | 'W' | '%' | '@' => 0.9,
+            c if c.is_ascii_uppercase() => 0.7,
+            c if c.is_ascii() => 0.6,
+            _ => 1.0,
+        })
+        .sum();
+    em * font_size
+}
+
+fn square(rank: usize) -> &'static str {
+    SQUARES.get(rank).copied().unwrap_or(OTHER_SQUARE)
+}
+
+/// Splits `width` cells among `stats` by percentage, rounding so that the counts add up to `width`.
+fn bar_cells(stats: &[LanguageStat], width: usize) -> Vec<usize> {
+    let exact: Vec<f64> = stats
+        .iter()
+        .map(|stat| stat.percentage / 100.0 * width as f64)
+        .collect();
+    let mut cells: Vec<usize> = exact.iter().map(|v| v.floor() as usize).collect();
+
+    // hand out the remaining cells to the largest remainders
+    let mut order: Vec<usize> = (0..stats.len()).collect();
+    order.sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+    let left = width.saturating_sub(cells.iter().sum());
+    for &i in order.iter().take(left) {
+        cells[i] += 1;
+    }
+
+    cells
+}
+
+/// Formats `n` with `,` between groups of three digits.
+fn group_digits(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Formats `n` with `,` between groups of three digits and a sign unless it is `0`,
+/// like the changes in [`markdown_diff`].
+/// ```rust
+/// use languatage::render::signed_digits;
+///
+/// assert_eq!(signed_digits(1234), "+1,234");
+/// assert_eq!(signed_digits(-5), "-5");
+/// ```
+pub fn signed_digits(n: i64) -> String {
+    match n {
+        0 => "0".into(),
+        1.. => format!("+{}", group_digits(n.unsigned_abs())),
+        _ => format!("-{}", group_digits(n.unsigned_abs())),
+    }
+}
+
+/// Escapes table cell separators, and HTML that GitHub would otherwise render.
+fn markdown_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('|', "\\|")
+}
+
+fn xml_escape(s: &str) -> String {
+    html_escape(s).replace('\'', "&apos;")
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> Vec<LanguageStat> {
+        vec![
+            LanguageStat {
+                lang: "Rust".into(),
+                size: 1234567,
+                percentage: 75.0,
+                files: 3,
+                ..Default::default()
+            },
+            LanguageStat {
+                lang: "C<C++>".into(),
+                size: 411522,
+                percentage: 25.0,
+                files: 1,
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn markdown_test() {
+        assert_eq!(
+            markdown(&stats(), &MarkdownOptions::default()),
+            "| Language | Percentage | Files | Size |
+| :-- | --: | --: | --: |
+| Rust | 75.00% | 3 | 1,234,567 |
+| C&lt;C++&gt; | 25.00% | 1 | 411,522 |
+"
+        );
+
+        let options = MarkdownOptions {
+            lines: true,
+            bar: true,
+        };
+        let out = markdown(&stats(), &options);
+        let mut lines = out.lines();
+        assert_eq!(lines.next().unwrap(), "🟦".repeat(15) + &"🟧".repeat(5));
+        assert_eq!(lines.nth(1).unwrap().matches('|').count(), 9);
+        assert!(out.contains("| 🟦 Rust | 75.00% | 3 | 1,234,567 | 0 | 0 | 0 | 0 |"));
+    }
+
+    #[test]
+    fn markdown_diff_test() {
+        let mut new = stats();
+        new[0].size += 1000;
+        new[0].percentage = 100.0;
+        new.truncate(1);
+        new.push(LanguageStat {
+            lang: "Go".into(),
+            size: 10,
+            files: 1,
+            ..Default::default()
+        });
+        let diffs = crate::diff_stats(&stats(), &new);
+
+        assert_eq!(
+            markdown_diff(&diffs, false),
+            "**New languages:** Go
+
+**Removed languages:** C&lt;C++&gt;
+
+| Language | Change | Percentage | Files | Size |
+| :-- | :-- | --: | --: | --: |
+| Rust | changed | +25.00 pp | 0 | +1,000 |
+| Go | added | +0.00 pp | +1 | +10 |
+| C&lt;C++&gt; | removed | -25.00 pp | -1 | -411,522 |
+"
+        );
+    }
+
+    #[test]
+    fn html_test() {
+        let options = HtmlOptions {
+            title: "a & b".into(),
+            lines: false,
+        };
+        let out = html(&stats(), &options);
+
+        assert!(out.starts_with("<!DOCTYPE html>"));
+        assert!(out.contains("<title>a &amp; b</title>"));
+        assert!(out.contains(&format!(
+            r#"<span style="width: 75.00%; background: {}" title="Rust 75.00%">"#,
+            color("Rust")
+        )));
+        assert!(out.contains("<td>C&lt;C++&gt;</td>"));
+        assert!(!out.contains("<th>Lines</th>"));
+    }
+
+    #[test]
+    fn svg_test() {
+        let bar = svg_bar(&stats(), &SvgOptions::default());
+        assert!(bar.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="400" "#));
+        assert!(bar.contains(r#"<rect x="0.00" width="300.00" height="8""#));
+        assert!(bar.contains(r#"<rect x="300.00" width="100.00" height="8""#));
+        assert!(bar.contains(">C&lt;C++&gt; 25.0%</text>"));
+
+        let narrow = svg_bar(&stats(), &SvgOptions { width: 100 });
+        assert_eq!(narrow.matches(r#"y="28.0""#).count(), 1);
+        assert_eq!(narrow.matches(r#"y="48.0""#).count(), 1);
+
+        let badge = svg_badge(&stats());
+        assert!(badge.contains(r#"aria-label="Rust: 75%""#));
+        assert!(badge.contains(&format!(r#"fill="{}""#, color("Rust"))));
+        assert!(svg_badge(&[]).contains(r#"aria-label="languages: none""#));
+
+        // ids differ between images, so they can be inlined into the same page
+        let id = |svg: &str| svg.split(r#"<clipPath id=""#).nth(1).unwrap()[..20].to_string();
+        assert!(bar.contains(&format!(r#"clip-path="url(#{}"#, id(&bar))));
+        assert_eq!(id(&bar), id(&svg_bar(&stats(), &SvgOptions::default())));
+        assert_ne!(id(&bar), id(&narrow));
+        assert!(badge.contains(&format!(r#"clip-path="url(#{}"#, id(&badge))));
+        assert_ne!(id(&badge), id(&svg_badge(&[])));
+    }
+
+    #[test]
+    fn text_color_test() {
+        assert_eq!(text_color("#f1e05a"), "#1f2328");
+        assert_eq!(text_color("#3572a5"), "#fff");
+        assert_eq!(text_color("#fff"), "#1f2328");
+
+        let stat = |color: &str| LanguageStat {
+            lang: "Rust".into(),
+            color: Some(color.into()),
+            ..Default::default()
+        };
+        assert_eq!(stat_color(&stat("#dea584")), "#dea584");
+        assert_eq!(stat_color(&stat("#abc")), "#abc");
+        assert_eq!(stat_color(&stat("red\"><script>")), color("Rust"));
+    }
+
+    #[test]
+    fn bar_cells_test() {
+        let stat = |percentage| LanguageStat {
+            percentage,
+            ..Default::default()
+        };
+        let stats = [stat(50.0), stat(30.0), stat(17.5), stat(2.5)];
+        assert_eq!(bar_cells(&stats, 20), [10, 6, 4, 0]);
+        assert_eq!(bar_cells(&stats, 10), [5, 3, 2, 0]);
+        assert_eq!(bar_cells(&[], 20), Vec::<usize>::new());
+        assert_eq!(group_digits(0), "0");
+        assert_eq!(group_digits(1000), "1,000");
+    }
+}
@@ -0,0 +1,138 @@
+//! Language hints found in file content.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Number of lines at the start and the end of a file that are searched for modelines.
+const MODELINE_LINES: usize = 5;
+
+/// Returns the interpreter named by the shebang line of `content`,
+/// e.g. `python3` for `#!/usr/bin/env python3`.
+pub(crate) fn shebang(content: &[u8]) -> Option<String> {
+    let line = content.strip_prefix(b"#!")?;
+    let line = line.split(|&b| b == b'\n').next()?;
+    let line = String::from_utf8_lossy(line);
+
+    let mut args = line.split_whitespace();
+    let mut interpreter = file_name(args.next()?);
+
+    if interpreter == "env" {
+        // skip options and variable assignments like `env -S VAR=1 python3`
+        interpreter = args
+            .find(|arg| !arg.starts_with('-') && !arg.contains('='))
+            .map(file_name)?;
+    }
+
+    Some(interpreter.to_string())
+}
+
+/// Returns `interpreter` without a trailing version, e.g. `python` for `python3.11`.
+pub(crate) fn strip_version(interpreter: &str) -> &str {
+    interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')
+}
+
+/// Returns the file type named by a Vim or Emacs modeline in the first or last lines of
+/// a file, e.g. `ruby` for `# vim: set ft=ruby:` or `python` for `# -*- mode: python -*-`.
+///
+/// `head` is the start of the file, and `tail` its end, or `None` if `head` is the whole file.
+pub(crate) fn modeline(head: &[u8], tail: Option<&[u8]>) -> Option<String> {
+    let head = String::from_utf8_lossy(head);
+    let head: Vec<_> = head.lines().collect();
+    let tail = tail.map(String::from_utf8_lossy);
+    let tail: Vec<_> = match &tail {
+        // the first line of the end may be cut off
+        Some(tail) => tail.lines().skip(1).collect(),
+        None => head.iter().skip(MODELINE_LINES).copied().collect(),
+    };
+
+    head.iter()
+        .take(MODELINE_LINES)
+        .chain(tail.iter().skip(tail.len().saturating_sub(MODELINE_LINES)))
+        .find_map(|line| vim_modeline(line).or_else(|| emacs_modeline(line)))
+}
+
+fn vim_modeline(line: &str) -> Option<String> {
+    static VIM: OnceLock<Regex> = OnceLock::new();
+    let vim = VIM.get_or_init(|| {
+        Regex::new(r"(?:^|\s)(?:vi|vim|Vim|ex)(?:[<=>]?\d+)?:.*?(?:^|[\s:])(?:ft|filetype|syntax)=([\w+#-]+)")
+            .expect("vim modeline regex is invalid")
+    });
+
+    Some(vim.captures(line)?[1].to_string())
+}
+
+fn emacs_modeline(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (modeline, _) = rest.split_once("-*-")?;
+
+    if !modeline.contains(':') {
+        return Some(modeline.trim().to_string()).filter(|v| !v.is_empty());
+    }
+
+    modeline.split(';').find_map(|variable| {
+        let (key, value) = variable.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("mode")
+            .then(|| value.trim().to_string())
+    })
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shebang_test() {
+        let shebang = |v: &str| shebang(v.as_bytes());
+
+        assert_eq!(shebang("#!/bin/bash\necho"), Some("bash".into()));
+        assert_eq!(shebang("#!/usr/bin/env python3\n"), Some("python3".into()));
+        assert_eq!(
+            shebang("#! /usr/bin/env -S node --harmony"),
+            Some("node".into())
+        );
+        assert_eq!(shebang("#!/usr/bin/env FOO=1 ruby -w"), Some("ruby".into()));
+        assert_eq!(shebang("#!/usr/bin/env"), None);
+        assert_eq!(shebang("echo\n#!/bin/sh"), None);
+
+        assert_eq!(strip_version("python3.11"), "python");
+        assert_eq!(strip_version("bash"), "bash");
+    }
+
+    #[test]
+    fn modeline_test() {
+        let modeline = |v: &str| modeline(v.as_bytes(), None);
+
+        assert_eq!(modeline("# vim: set ft=ruby:\n"), Some("ruby".into()));
+        assert_eq!(
+            modeline("x\n/* vim: set filetype=javascript: */"),
+            Some("javascript".into())
+        );
+        assert_eq!(modeline("# -*- mode: python -*-"), Some("python".into()));
+        assert_eq!(
+            modeline("# -*- coding: utf-8; mode: Perl -*-"),
+            Some("Perl".into())
+        );
+        assert_eq!(modeline(";; -*- lisp -*-"), Some("lisp".into()));
+        assert_eq!(modeline("# -*- coding: utf-8 -*-"), None);
+        assert_eq!(
+            modeline(&format!("{}# vim: ft=sh", "\n".repeat(20))),
+            Some("sh".into())
+        );
+        assert_eq!(
+            modeline(&format!("\n\n\n\n\n\n# vim: ft=sh{}", "\n".repeat(20))),
+            None
+        );
+
+        let with_tail =
+            |head: &str, tail: &str| super::modeline(head.as_bytes(), Some(tail.as_bytes()));
+
+        assert_eq!(with_tail("x\n", "x\n# vim: ft=sh"), Some("sh".into()));
+        assert_eq!(with_tail("# vim: ft=sh\n", "x\n"), Some("sh".into()));
+        assert_eq!(with_tail("x\n", "# vim: ft=sh\nx"), None);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::{
+    config::CommonConfig,
+    error::{Error, Result, SkipReason, Skipped},
+    patterns::Patterns,
+};
+use ignore::{DirEntry, WalkBuilder, WalkState};
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The result of [`walk`].
+pub(crate) struct Walked<R> {
+    /// Files and the values produced for them, sorted by path.
+    pub(crate) files: Vec<(PathBuf, R)>,
+    /// Paths that were skipped because of errors, sorted by path.
+    pub(crate) skipped: Vec<Skipped>,
+}
+
+/// Calls `f` with every file under `path` on `threads` threads, skipping dot directories,
+/// paths matched by `common.ignore`, files not matched by a non-empty `common.include`
+/// and, if `common.gitignore` is set, files ignored by git.
+/// `0` threads picks a number automatically.
+///
+/// The values produced by `f` and the paths that were skipped because of errors are both
+/// sorted by path, so the result doesn't depend on the order in which the threads visit
+/// the files.
+pub(crate) fn walk<P, R, F>(
+    path: P,
+    common: &CommonConfig,
+    threads: usize,
+    f: F,
+) -> Result<Walked<R>>
+where
+    P: AsRef<Path>,
+    R: Send,
+    F: Fn(&DirEntry) -> Result<Option<R>, SkipReason> + Sync,
+{
+    let path = path.as_ref();
+    let ignore = Patterns::new(path, &common.ignore).map_err(Error::invalid_pattern)?;
+    let include = Patterns::new(path, &common.include).map_err(Error::invalid_pattern)?;
+    let results = Mutex::new(Vec::new());
+    let skipped = Mutex::new(Vec::new());
+
+    WalkBuilder::new(path)
+        .standard_filters(false)
+        .git_ignore(common.gitignore)
+        .git_global(common.gitignore)
+        .git_exclude(common.gitignore)
+        .ignore(common.gitignore)
+        .parents(common.gitignore)
+        .require_git(false)
+        .threads(threads)
+        .filter_entry(move |entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+
+            let is_dir = entry.file_type().is_some_and(|v| v.is_dir());
+
+            if is_dir && entry.file_name().as_encoded_bytes().starts_with(b".") {
+                return false;
+            }
+
+            if ignore.is_match(entry.path(), is_dir) {
+                return false;
+            }
+
+            is_dir || include.is_empty() || include.is_match_or_any_parents(entry.path(), false)
+        })
+        .build_parallel()
+        .run(|| {
+            let f = &f;
+            let results = &results;
+            let skipped = &skipped;
+            Box::new(move |entry| {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        skip_error(path, err, &mut skipped.lock().unwrap());
+                        return WalkState::Continue;
+                    }
+                };
+
+                if !entry.file_type().is_some_and(|v| v.is_file()) {
+                    return WalkState::Continue;
+                }
+
+                match f(&entry) {
+                    Ok(Some(result)) => {
+                        results.lock().unwrap().push((entry.into_path(), result));
+                    }
+                    Ok(None) => {}
+                    Err(reason) => skipped.lock().unwrap().push(Skipped {
+                        path: entry.into_path(),
+                        reason,
+                    }),
+                }
+
+                WalkState::Continue
+            })
+        });
+
+    let mut files = results.into_inner().unwrap();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut skipped = skipped.into_inner().unwrap();
+    skipped.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(Walked { files, skipped })
+}
+
+/// Records the paths in a walk error, or `root` if it has none.
+fn skip_error(root: &Path, err: ignore::Error, skipped: &mut Vec<Skipped>) {
+    match err {
+        ignore::Error::Partial(errs) => {
+            for err in errs {
+                skip_error(root, err, skipped);
+            }
+        }
+        ignore::Error::WithPath { path, err } => skipped.push(Skipped {
+            path,
+            reason: SkipReason::Walk(err.to_string()),
+        }),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            skip_error(root, *err, skipped)
+        }
+        err => skipped.push(Skipped {
+            path: root.to_path_buf(),
+            reason: SkipReason::Walk(err.to_string()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    use std::path::MAIN_SEPARATOR;
+
+    #[test]
+    fn walk_test() {
+        let config = Config::default();
+        let paths: Vec<_> = walk(".", &config.common, 0, |_| Ok(Some(())))
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert!(paths.windows(2).all(|v| v[0] < v[1]));
+
+        let paths: Vec<_> = paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+
+        assert!(paths.contains(&format!(".{MAIN_SEPARATOR}Cargo.toml")));
+        assert!(paths.contains(&format!(".{0}src{0}lib.rs", MAIN_SEPARATOR)));
+        assert!(!paths
+            .iter()
+            .any(|path| path.contains(&format!("{}.git{}", MAIN_SEPARATOR, MAIN_SEPARATOR))));
+        assert!(!paths
+            .iter()
+            .any(|path| path.contains(&format!("{}target{}", MAIN_SEPARATOR, MAIN_SEPARATOR))));
+    }
+
+    #[test]
+    fn walk_gitignore_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let files = [
+            "main.rs",
+            "dist/bundle.js",
+            "api.pb.go",
+            "keep.pb.go",
+            "sub/local.rs",
+            "sub/deep/nested.rs",
+            "excluded.rs",
+            "scratch.rs",
+        ];
+        for file in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::create_dir_all(root.join(".git/info")).unwrap();
+        std::fs::write(root.join(".gitignore"), "dist/\n*.pb.go\n!keep.pb.go\n").unwrap();
+        std::fs::write(root.join("sub/.gitignore"), "local.rs\n").unwrap();
+        std::fs::write(root.join(".git/info/exclude"), "excluded.rs\n").unwrap();
+        std::fs::write(root.join(".ignore"), "scratch.rs\n").unwrap();
+
+        let walk_files = |gitignore| {
+            let common = CommonConfig {
+                ignore: vec![],
+                include: vec![],
+                gitignore,
+                modelines: false,
+            };
+            walk(root, &common, 0, |_| Ok(Some(())))
+                .unwrap()
+                .files
+                .into_iter()
+                .map(|(path, _)| path.strip_prefix(root).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+
+        let expected: Vec<_> = [
+            ".gitignore",
+            ".ignore",
+            "keep.pb.go",
+            "main.rs",
+            "sub/.gitignore",
+            "sub/deep/nested.rs",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(walk_files(true), expected);
+        assert_eq!(walk_files(false).len(), files.len() + 3);
+    }
+
+    #[test]
+    fn walk_patterns_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let files = [
+            "vendor/a.go",
+            "lib/vendor/b.go",
+            "packages/app/build/c.js",
+            "packages/app/src/d.ts",
+            "packages/app/src/e.generated.ts",
+            "tools/f.rs",
+        ];
+        for file in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let common = CommonConfig {
+            ignore: ["/vendor", "packages/*/build", "**/*.generated.ts"]
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            include: vec!["lib".into(), "packages".into()],
+            gitignore: false,
+            modelines: false,
+        };
+        let paths: Vec<_> = walk(root, &common, 0, |_| Ok(Some(())))
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|(path, _)| path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+
+        let expected: Vec<_> = ["lib/vendor/b.go", "packages/app/src/d.ts"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(paths, expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_non_utf8_test() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let latin1 = root.join(OsStr::from_bytes(b"caf\xe9"));
+        // some file systems only accept UTF-8 names
+        if std::fs::create_dir_all(latin1.join("build")).is_err() {
+            return;
+        }
+        std::fs::write(latin1.join(OsStr::from_bytes(b"r\xe9sum\xe9.rs")), "").unwrap();
+        std::fs::write(latin1.join("build/a.rs"), "").unwrap();
+
+        let common = CommonConfig {
+            ignore: vec!["build".into()],
+            include: vec![],
+            gitignore: false,
+            modelines: false,
+        };
+        let Walked { files, skipped } = walk(root, &common, 0, |_| Ok(Some(()))).unwrap();
+
+        assert!(skipped.is_empty());
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].0,
+            latin1.join(OsStr::from_bytes(b"r\xe9sum\xe9.rs"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_skipped_test() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let locked = root.join("locked");
+        std::fs::create_dir_all(&locked).unwrap();
+        std::fs::write(locked.join("a.rs"), "").unwrap();
+        std::fs::write(root.join("b.rs"), "").unwrap();
+        std::fs::write(root.join("c.rs"), "").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        // permissions don't apply to root
+        if std::fs::read_dir(&locked).is_ok() {
+            return;
+        }
+
+        let Walked { files, skipped } =
+            walk(root, &Config::default().common, 0, |entry| {
+                match entry.file_name() == "c.rs" {
+                    true => Err(SkipReason::Read("c".into())),
+                    false => Ok(Some(())),
+                }
+            })
+            .unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].path, root.join("c.rs"));
+        assert_eq!(skipped[1].path, locked);
+        assert!(matches!(skipped[1].reason, SkipReason::Walk(_)));
+    }
+}